@@ -1,19 +1,14 @@
-use parser::{Parser, ParserFactory};
+use parser::{Compression, Format, Transaction};
+use std::path::Path;
 
 pub fn compare(
-    file1: String,
-    file2: String,
-    parser_factory1: impl ParserFactory,
-    parser_factory2: impl ParserFactory,
+    file1: &Path,
+    format1: Format,
+    file2: &Path,
+    format2: Format,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file1 = std::fs::File::open(file1)?;
-    let mut file2 = std::fs::File::open(file2)?;
-
-    let parser1 = parser_factory1.create_parser();
-    let parser2 = parser_factory2.create_parser();
-
-    let transactions1 = parser1.read_from(&mut file1)?;
-    let transactions2 = parser2.read_from(&mut file2)?;
+    let transactions1 = read_file(file1, format1)?;
+    let transactions2 = read_file(file2, format2)?;
 
     if transactions1.len() != transactions2.len() {
         println!("Files have different number of transactions");
@@ -33,3 +28,8 @@ pub fn compare(
 
     Ok(())
 }
+
+fn read_file(path: &Path, format: Format) -> Result<Vec<Transaction>, Box<dyn std::error::Error>> {
+    let file = Compression::open(path)?;
+    Ok(format.read_iter(file).collect::<Result<Vec<_>, _>>()?)
+}
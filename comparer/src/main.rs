@@ -1,59 +1,40 @@
 use clap::Parser;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
 use comparer::compare;
-use parser::{BinParserFactory, CsvParserFactory, TxtParserFactory};
-use serde::Serialize;
+use parser::Format;
+use std::path::{Path, PathBuf};
 
-#[derive(clap::ValueEnum, Clone, Debug, Serialize)]
-enum Format {
-    Csv,
-    Txt,
-    Bin,
+fn format_parser() -> impl TypedValueParser<Value = Format> {
+    PossibleValuesParser::new(Format::NAMES).map(|s| s.parse::<Format>().unwrap())
 }
 
 #[derive(clap::Parser, Debug)]
 struct Args {
     #[arg(long)]
-    file1: String,
+    file1: PathBuf,
     #[arg(long)]
-    file2: String,
-    #[arg(long, value_enum)]
-    format1: Format,
-    #[arg(long, value_enum)]
-    format2: Format,
+    file2: PathBuf,
+    /// Format of the first file; detected from the file extension when omitted.
+    #[arg(long, value_parser = format_parser())]
+    format1: Option<Format>,
+    /// Format of the second file; detected from the file extension when omitted.
+    #[arg(long, value_parser = format_parser())]
+    format2: Option<Format>,
+}
+
+fn detect_format(path: &Path, format: Option<Format>) -> Result<Format, String> {
+    format
+        .or_else(|| Format::from_path(path))
+        .ok_or(format!("Cannot detect format of {}", path.display()))
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    match (args.format1, args.format2) {
-        (Format::Csv, Format::Txt) => {
-            compare(args.file1, args.file2, CsvParserFactory, TxtParserFactory)
-        }
-        (Format::Txt, Format::Csv) => {
-            compare(args.file1, args.file2, TxtParserFactory, CsvParserFactory)
-        }
-        (Format::Csv, Format::Csv) => {
-            compare(args.file1, args.file2, CsvParserFactory, CsvParserFactory)
-        }
-        (Format::Txt, Format::Txt) => {
-            compare(args.file1, args.file2, TxtParserFactory, TxtParserFactory)
-        }
-        (Format::Bin, Format::Csv) => {
-            compare(args.file1, args.file2, BinParserFactory, CsvParserFactory)
-        }
-        (Format::Bin, Format::Txt) => {
-            compare(args.file1, args.file2, BinParserFactory, TxtParserFactory)
-        }
-        (Format::Bin, Format::Bin) => {
-            compare(args.file1, args.file2, BinParserFactory, BinParserFactory)
-        }
-        (Format::Csv, Format::Bin) => {
-            compare(args.file1, args.file2, CsvParserFactory, BinParserFactory)
-        }
-        (Format::Txt, Format::Bin) => {
-            compare(args.file1, args.file2, TxtParserFactory, BinParserFactory)
-        }
-    }?;
+    let format1 = detect_format(&args.file1, args.format1)?;
+    let format2 = detect_format(&args.file2, args.format2)?;
+
+    compare(&args.file1, format1, &args.file2, format2)?;
 
     Ok(())
 }
@@ -0,0 +1,187 @@
+//! The `batch` module provides conversion of whole directories of transaction files.
+//!
+//! Files are collected recursively from the input directory and converted in parallel.
+//! Every output keeps the relative path of its input, with the extension replaced by the
+//! output format and the compression extension kept, e.g. `a/records.csv.gz` is
+//! converted to `a/records.bin.gz`. Inputs that would be converted to the same output, e.g.
+//! `records.csv` and `records.txt`, fail instead of overwriting each other.
+
+use crate::{ConvertOptions, Input, Stats, convert};
+use parser::{Compression, Format};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use walkdir::WalkDir;
+
+/// The `BatchSummary` struct is the outcome of converting a directory.
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    /// Inputs that were converted successfully.
+    pub converted: Vec<PathBuf>,
+    /// Inputs that failed to convert, with the error.
+    pub failed: Vec<(PathBuf, String)>,
+    /// Inputs that were skipped because their format is unknown.
+    pub skipped: Vec<PathBuf>,
+    /// Statistics of all converted inputs.
+    pub stats: Stats,
+}
+
+/// Converts every file in `input_dir` with a detected format into `output_dir`.
+///
+/// `detect` returns the format of a file, or `None` to skip it.
+pub fn convert_dir(
+    input_dir: &Path,
+    output_dir: &Path,
+    output_format: Format,
+    detect: impl Fn(&Path) -> Option<Format> + Sync,
+    options: &ConvertOptions,
+) -> Result<BatchSummary, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let mut summary = BatchSummary::default();
+    let mut inputs = vec![];
+
+    for entry in WalkDir::new(input_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        match detect(entry.path()) {
+            Some(format) => inputs.push(Input {
+                path: entry.into_path(),
+                format,
+            }),
+            None => summary.skipped.push(entry.into_path()),
+        }
+    }
+
+    let outputs = inputs
+        .iter()
+        .map(|input| {
+            let relative = input.path.strip_prefix(input_dir).unwrap_or(&input.path);
+            output_path(&output_dir.join(relative), output_format)
+        })
+        .collect::<Vec<_>>();
+    let mut counts = HashMap::new();
+    for output in &outputs {
+        *counts.entry(output).or_insert(0) += 1;
+    }
+
+    let results = inputs
+        .par_iter()
+        .zip(&outputs)
+        .map(|(input, output)| {
+            if counts[output] > 1 {
+                return Err(format!(
+                    "Output {} is also the output of another input",
+                    output.display()
+                ));
+            }
+            convert_file(input, output, output_format, options)
+        })
+        .collect::<Vec<_>>();
+
+    for (input, result) in inputs.into_iter().zip(results) {
+        match result {
+            Ok(stats) => {
+                summary.stats.merge(&stats);
+                summary.converted.push(input.path);
+            }
+            Err(e) => summary.failed.push((input.path, e)),
+        }
+    }
+
+    summary.stats.elapsed = started.elapsed();
+
+    Ok(summary)
+}
+
+/// Replaces the format extension of a path, keeping its compression extension.
+fn output_path(path: &Path, format: Format) -> PathBuf {
+    let compression = Compression::from_path(path);
+    let path = Compression::strip_extension(path).with_extension(format.to_string());
+    match compression.extension() {
+        Some(extension) => path.with_added_extension(extension),
+        None => path,
+    }
+}
+
+fn convert_file(
+    input: &Input,
+    output: &Path,
+    output_format: Format,
+    options: &ConvertOptions,
+) -> Result<Stats, String> {
+    if let Some(parent) = output.parent()
+        && !options.dry_run
+    {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let output = output
+        .to_str()
+        .ok_or(format!("Invalid output path: {}", output.display()))?;
+    convert(std::slice::from_ref(input), output, output_format, options).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_output_paths() {
+        assert_eq!(
+            output_path(Path::new("out/a/records.csv"), Format::Bin),
+            Path::new("out/a/records.bin")
+        );
+        assert_eq!(
+            output_path(Path::new("out/records.csv.gz"), Format::Bin),
+            Path::new("out/records.bin.gz")
+        );
+        assert_eq!(
+            output_path(Path::new("out/records"), Format::Xml),
+            Path::new("out/records.xml")
+        );
+    }
+
+    #[test]
+    fn it_converts_directories() {
+        let input_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let input = |name: &str, sample: &str| {
+            let path = input_dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::copy(sample, path).unwrap();
+        };
+        input("a/records.csv", "../samples/records_example.csv");
+        input("b/records.csv", "../samples/records_example.csv");
+        input("b/records.txt", "../samples/records_example.txt");
+        input("notes.md", "../README.md");
+
+        let summary = convert_dir(
+            input_dir.path(),
+            output_dir.path(),
+            Format::Bin,
+            Format::from_path,
+            &ConvertOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(summary.converted, [input_dir.path().join("a/records.csv")]);
+        assert_eq!(summary.skipped, [input_dir.path().join("notes.md")]);
+        let failed = summary
+            .failed
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failed,
+            [
+                input_dir.path().join("b/records.csv"),
+                input_dir.path().join("b/records.txt"),
+            ]
+        );
+        assert!(output_dir.path().join("a/records.bin").exists());
+        assert!(!output_dir.path().join("b/records.bin").exists());
+        assert_eq!(summary.stats.read, 1000);
+    }
+}
@@ -1,19 +1,708 @@
-use parser::{Parser, ParserFactory};
+mod batch;
+mod sort;
+mod split;
+mod stats;
+mod transform;
+mod verify;
 
+#[cfg(feature = "sqlite")]
+use parser::SqliteParser;
+#[cfg(feature = "avro")]
+use parser::{AvroCodec, AvroParser};
+use parser::{
+    BinIndex, BinParser, BinVersion, Compression, CsvColumns, CsvParser, Filter, FixedWidthLayout,
+    FixedWidthParser, Format, Parser, ParserError, Transaction, Transactions, XmlParser, XmlStyle,
+};
+#[cfg(feature = "parquet")]
+use parser::{ParquetCompression, ParquetParser};
+#[cfg(feature = "xlsx")]
+use parser::{XlsxParser, XlsxSheets};
+use std::cell::{Cell, RefCell};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tempfile::TempPath;
+
+pub use batch::{BatchSummary, convert_dir};
+pub use sort::{DEFAULT_SORT_BUFFER, SortKey, SortOrder};
+pub use split::Split;
+pub use stats::Stats;
+pub use transform::{BuiltinTransform, TimestampUnit, Transform};
+pub use verify::{Verification, verify};
+
+/// A file to read transactions from.
+pub struct Input {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The format of the file.
+    pub format: Format,
+}
+
+/// Options that control how transactions are converted.
+pub struct ConvertOptions {
+    /// Only transactions matching the filter are written to the output.
+    pub filter: Option<Filter>,
+    /// Transforms applied in order to every transaction that passes the filter.
+    pub transforms: Vec<Box<dyn Transform + Send + Sync>>,
+    /// Keys to sort the output by; the input order is kept when empty.
+    pub sort_by: Vec<SortKey>,
+    /// The number of transactions sorted, or split by date or type, in memory before spilling
+    /// to disk.
+    pub sort_buffer: usize,
+    /// How to split the output into several files.
+    pub split: Option<Split>,
+    /// Columns of CSV inputs; the standard columns are expected when not set.
+    pub input_columns: Option<CsvColumns>,
+    /// Columns of a CSV output; all fields are written when not set.
+    pub output_columns: Option<CsvColumns>,
+    /// Layout of fixed-width inputs; the default layout is expected when not set.
+    pub input_layout: Option<FixedWidthLayout>,
+    /// Layout of a fixed-width output; the default layout is used when not set.
+    pub output_layout: Option<FixedWidthLayout>,
+    /// How fields are written to an XML output.
+    pub xml_style: XmlStyle,
+    /// The version of a BIN output.
+    pub bin_version: BinVersion,
+    /// Writes a CRC32C of every record and a SHA-256 of the file to a BIN v2 output.
+    pub bin_checksums: bool,
+    /// Writes an index sidecar next to a BIN output, e.g. `records.bin.idx`.
+    pub bin_index: bool,
+    /// The maximum number of transactions in a row group of a Parquet output.
+    #[cfg(feature = "parquet")]
+    pub parquet_row_group_size: usize,
+    /// How the columns of a Parquet output are compressed.
+    #[cfg(feature = "parquet")]
+    pub parquet_compression: ParquetCompression,
+    /// How the blocks of an Avro output are compressed.
+    #[cfg(feature = "avro")]
+    pub avro_codec: AvroCodec,
+    /// The table of SQLite databases that transactions are written to and read from.
+    #[cfg(feature = "sqlite")]
+    pub sqlite_table: String,
+    /// A query whose rows are read from SQLite inputs instead of the whole table.
+    #[cfg(feature = "sqlite")]
+    pub sqlite_query: Option<String>,
+    /// How transactions of an XLSX output are distributed over sheets.
+    #[cfg(feature = "xlsx")]
+    pub xlsx_sheets: XlsxSheets,
+    /// Reads and validates the inputs without writing any output.
+    pub dry_run: bool,
+    /// Replaces existing output files; conversion fails on an existing output otherwise.
+    pub overwrite: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            transforms: vec![],
+            sort_by: vec![],
+            sort_buffer: DEFAULT_SORT_BUFFER,
+            split: None,
+            input_columns: None,
+            output_columns: None,
+            input_layout: None,
+            output_layout: None,
+            xml_style: XmlStyle::default(),
+            bin_version: BinVersion::default(),
+            bin_checksums: false,
+            bin_index: false,
+            #[cfg(feature = "parquet")]
+            parquet_row_group_size: ParquetParser::DEFAULT_ROW_GROUP_SIZE,
+            #[cfg(feature = "parquet")]
+            parquet_compression: ParquetCompression::default(),
+            #[cfg(feature = "avro")]
+            avro_codec: AvroCodec::default(),
+            #[cfg(feature = "sqlite")]
+            sqlite_table: SqliteParser::DEFAULT_TABLE.to_string(),
+            #[cfg(feature = "sqlite")]
+            sqlite_query: None,
+            #[cfg(feature = "xlsx")]
+            xlsx_sheets: XlsxSheets::default(),
+            dry_run: false,
+            overwrite: true,
+        }
+    }
+}
+
+/// Converts the transactions of all inputs, in order, to the output format.
+///
+/// When the output is split, `output` is a template for the output file names.
+/// Outputs are written to temporary files next to them and moved into place only
+/// when the whole conversion succeeds, so a failed conversion leaves no partial output.
+/// Returns statistics about the converted transactions and files.
 pub fn convert(
-    input: String,
-    output: String,
-    input_parser: impl ParserFactory,
-    output_parser: impl ParserFactory,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut input = std::fs::File::open(input)?;
-    let mut output = std::fs::File::create(output)?;
+    inputs: &[Input],
+    output: &str,
+    output_format: Format,
+    options: &ConvertOptions,
+) -> Result<Stats, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+
+    if options.output_columns.is_some() && output_format != Format::Csv {
+        return Err(format!("Columns cannot be selected for {} output", output_format).into());
+    }
+    if options.output_layout.is_some() && output_format != Format::FixedWidth {
+        return Err(format!("A layout cannot be used for {} output", output_format).into());
+    }
+    if options.bin_index && output_format != Format::Bin {
+        return Err(format!("An index cannot be written for {} output", output_format).into());
+    }
+    if options.bin_index && Compression::from_path(Path::new(output)) != Compression::None {
+        return Err("An index cannot be written for compressed output".into());
+    }
+    if let Some(Split::Bytes(_)) = options.split {
+        if is_container(output_format) {
+            return Err(format!("Output of {} cannot be split by bytes", output_format).into());
+        }
+        if Compression::from_path(Path::new(output)) != Compression::None {
+            return Err("Compressed output cannot be split by bytes".into());
+        }
+    }
+    if !options.overwrite
+        && !options.dry_run
+        && options.split.is_none()
+        && Path::new(output).exists()
+    {
+        return Err(format!("Output {} already exists", output).into());
+    }
+
+    let mut bytes_in = 0;
+    let files = inputs
+        .iter()
+        .map(|input| {
+            let codec = Codec::new(
+                input.format,
+                options.input_columns.as_ref(),
+                options.input_layout.as_ref(),
+                options,
+            );
+            bytes_in += std::fs::metadata(&input.path)?.len();
+            Ok((Compression::open(&input.path)?, codec))
+        })
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+
+    let read = Cell::new(0);
+    let stats = RefCell::new(Stats::default());
+
+    let mut transactions: Transactions = Box::new(
+        files
+            .into_iter()
+            .flat_map(|(file, codec)| codec.read_iter(file))
+            .inspect(|transaction| {
+                if transaction.is_ok() {
+                    read.set(read.get() + 1);
+                }
+            }),
+    );
+    if let Some(filter) = &options.filter {
+        transactions = Box::new(transactions.filter(|transaction| {
+            transaction
+                .as_ref()
+                .map_or(true, |transaction| filter.matches(transaction))
+        }));
+    }
+    if !options.transforms.is_empty() {
+        transactions = Box::new(transactions.map(|transaction| {
+            options
+                .transforms
+                .iter()
+                .try_fold(transaction?, |transaction, transform| {
+                    transform.apply(transaction)
+                })
+        }));
+    }
+    transactions = Box::new(transactions.inspect(|transaction| {
+        if let Ok(transaction) = transaction {
+            stats.borrow_mut().add(transaction);
+        }
+    }));
+
+    let mut outputs = vec![];
+    if options.dry_run {
+        for transaction in transactions {
+            transaction?;
+        }
+    } else {
+        if !options.sort_by.is_empty() {
+            transactions = sort::sort(transactions, &options.sort_by, options.sort_buffer)?;
+        }
+
+        let codec = Codec::new(
+            output_format,
+            options.output_columns.as_ref(),
+            options.output_layout.as_ref(),
+            options,
+        );
+        match &options.split {
+            Some(split) => split.write(
+                output,
+                |transactions| codec.size_of(transactions),
+                options.sort_buffer,
+                transactions,
+                |name, part| {
+                    outputs.push(write_file(name, &codec, options.bin_index, part)?);
+                    Ok(())
+                },
+            )?,
+            None => outputs.push(write_file(output, &codec, options.bin_index, transactions)?),
+        }
+    }
+
+    let bytes_out = persist_all(outputs, options.overwrite)?;
+
+    let mut stats = stats.into_inner();
+    stats.read = read.get();
+    stats.bytes_in = bytes_in;
+    stats.bytes_out = bytes_out;
+    stats.elapsed = started.elapsed();
+
+    Ok(stats)
+}
+
+/// Returns whether a format writes transactions into a container, such as a database or a
+/// workbook, whose size is not the sum of the sizes of single transactions.
+fn is_container(format: Format) -> bool {
+    match format {
+        #[cfg(feature = "arrow")]
+        Format::Arrow | Format::ArrowStream => true,
+        #[cfg(feature = "parquet")]
+        Format::Parquet => true,
+        #[cfg(feature = "avro")]
+        Format::Avro => true,
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => true,
+        #[cfg(feature = "xlsx")]
+        Format::Xlsx => true,
+        _ => false,
+    }
+}
+
+/// Writes transactions to a temporary file in the directory of `path`, and the index of a BIN
+/// file to another one when `bin_index` is set.
+fn write_file(
+    path: &str,
+    codec: &Codec,
+    bin_index: bool,
+    transactions: impl Iterator<Item = Result<Transaction, ParserError>>,
+) -> Result<PendingOutput, Box<dyn std::error::Error>> {
+    let path = PathBuf::from(path);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".converter-").suffix(".tmp");
+    // Temporary files are private by default; outputs get the usual permissions.
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o666));
+    let file = builder.tempfile_in(directory)?;
+
+    let compression = Compression::from_path(&path);
+    let (file, index) = match codec {
+        // Uncompressed BIN files are written to the file itself, which can seek back to fill in
+        // the record count of a v2 header.
+        Codec::Bin(bin) if compression == Compression::None => {
+            let mut file = file;
+            let index = if bin_index {
+                let mut index = BufWriter::new(builder.tempfile_in(directory)?);
+                bin.write_indexed(file.as_file_mut(), &mut index, transactions)?;
+                let index = index.into_inner()?;
+                index.as_file().sync_all()?;
+                Some(index.into_temp_path())
+            } else {
+                bin.write_file(file.as_file_mut(), transactions)?;
+                None
+            };
+            (file, index)
+        }
+        _ => {
+            let mut output = compression.encoder(BufWriter::new(file))?;
+            codec.write_iter(&mut output, transactions)?;
+            (output.finish()?.into_inner()?, None)
+        }
+    };
+    file.as_file().sync_all()?;
+    let size = file.as_file().metadata()?.len();
+
+    Ok(PendingOutput {
+        file: file.into_temp_path(),
+        index,
+        path,
+        size,
+    })
+}
+
+/// The `PendingOutput` struct is a written temporary file that is not yet in place.
+///
+/// The temporary files are removed when the output is dropped without being persisted.
+struct PendingOutput {
+    file: TempPath,
+    /// The index of a BIN file, which is persisted next to it.
+    index: Option<TempPath>,
+    path: PathBuf,
+    size: u64,
+}
+
+impl PendingOutput {
+    /// Returns the temporary files with the paths they are moved to.
+    fn into_files(self) -> Vec<(TempPath, PathBuf)> {
+        let mut files = vec![];
+        if let Some(index) = self.index {
+            files.push((index, BinIndex::sidecar_path(&self.path)));
+        }
+        files.push((self.file, self.path));
+        files
+    }
+}
+
+/// Moves all outputs into place and returns their total size.
+///
+/// Without `overwrite`, no output is moved when any of them exists. When moving an output
+/// fails, the outputs moved before it are removed again, unless they replaced a file. Once all
+/// outputs are moved, an index sidecar next to an output written without an index is removed,
+/// as it no longer matches.
+fn persist_all(
+    outputs: Vec<PendingOutput>,
+    overwrite: bool,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let size = outputs.iter().map(|output| output.size).sum();
+    let stale = outputs
+        .iter()
+        .filter(|output| output.index.is_none())
+        .map(|output| BinIndex::sidecar_path(&output.path))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    let files = outputs
+        .into_iter()
+        .flat_map(PendingOutput::into_files)
+        .collect::<Vec<_>>();
+    if !overwrite && let Some((_, path)) = files.iter().find(|(_, path)| path.exists()) {
+        return Err(format!("Output {} already exists", path.display()).into());
+    }
+
+    let mut created = vec![];
+    for (file, path) in files {
+        let exists = path.exists();
+        let result = if overwrite {
+            file.persist(&path)
+        } else {
+            file.persist_noclobber(&path)
+        };
+        if let Err(e) = result {
+            for path in created {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(format!("{}: {}", path.display(), e.error).into());
+        }
+        if !exists {
+            created.push(path);
+        }
+    }
+    for path in stale {
+        std::fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    Ok(size)
+}
+
+/// The `Codec` enum reads and writes a format, with selected columns for CSV,
+/// a selected layout for fixed-width files and selected options for XML, BIN, Parquet, Avro,
+/// SQLite and XLSX.
+enum Codec {
+    Csv(CsvParser),
+    FixedWidth(FixedWidthParser),
+    Xml(XmlParser),
+    Bin(BinParser),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetParser),
+    #[cfg(feature = "avro")]
+    Avro(AvroParser),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteParser),
+    #[cfg(feature = "xlsx")]
+    Xlsx(XlsxParser),
+    Format(Format),
+}
+
+impl Codec {
+    fn new(
+        format: Format,
+        columns: Option<&CsvColumns>,
+        layout: Option<&FixedWidthLayout>,
+        options: &ConvertOptions,
+    ) -> Self {
+        match (format, columns, layout) {
+            (Format::Csv, Some(columns), _) => Codec::Csv(CsvParser::new(columns.clone())),
+            (Format::FixedWidth, _, Some(layout)) => {
+                Codec::FixedWidth(FixedWidthParser::new(layout.clone()))
+            }
+            (Format::Xml, _, _) => Codec::Xml(XmlParser::new(options.xml_style)),
+            (Format::Bin, _, _) => {
+                Codec::Bin(BinParser::new(options.bin_version, options.bin_checksums))
+            }
+            #[cfg(feature = "parquet")]
+            (Format::Parquet, _, _) => Codec::Parquet(ParquetParser::new(
+                options.parquet_row_group_size,
+                options.parquet_compression,
+            )),
+            #[cfg(feature = "avro")]
+            (Format::Avro, _, _) => Codec::Avro(AvroParser::new(options.avro_codec)),
+            #[cfg(feature = "sqlite")]
+            (Format::Sqlite, _, _) => Codec::Sqlite(SqliteParser::new(
+                options.sqlite_table.clone(),
+                options.sqlite_query.clone(),
+            )),
+            #[cfg(feature = "xlsx")]
+            (Format::Xlsx, _, _) => Codec::Xlsx(XlsxParser::new(options.xlsx_sheets)),
+            _ => Codec::Format(format),
+        }
+    }
+
+    fn read_iter<'a>(&self, r: impl Read + 'a) -> Transactions<'a> {
+        match self {
+            Codec::Csv(csv) => csv.read_iter(r),
+            Codec::FixedWidth(fixed) => fixed.read_iter(r),
+            Codec::Xml(xml) => xml.read_iter(r),
+            Codec::Bin(bin) => bin.read_iter(r),
+            #[cfg(feature = "parquet")]
+            Codec::Parquet(parquet) => parquet.read_iter(r),
+            #[cfg(feature = "avro")]
+            Codec::Avro(avro) => avro.read_iter(r),
+            #[cfg(feature = "sqlite")]
+            Codec::Sqlite(sqlite) => sqlite.read_iter(r),
+            #[cfg(feature = "xlsx")]
+            Codec::Xlsx(xlsx) => xlsx.read_iter(r),
+            Codec::Format(format) => format.read_iter(r),
+        }
+    }
+
+    fn write_iter<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: impl Iterator<Item = Result<Transaction, ParserError>>,
+    ) -> Result<(), ParserError> {
+        match self {
+            Codec::Csv(csv) => csv.write_iter(writer, transactions),
+            Codec::FixedWidth(fixed) => fixed.write_iter(writer, transactions),
+            Codec::Xml(xml) => xml.write_iter(writer, transactions),
+            Codec::Bin(bin) => bin.write_iter(writer, transactions),
+            #[cfg(feature = "parquet")]
+            Codec::Parquet(parquet) => parquet.write_iter(writer, transactions),
+            #[cfg(feature = "avro")]
+            Codec::Avro(avro) => avro.write_iter(writer, transactions),
+            #[cfg(feature = "sqlite")]
+            Codec::Sqlite(sqlite) => sqlite.write_iter(writer, transactions),
+            #[cfg(feature = "xlsx")]
+            Codec::Xlsx(xlsx) => xlsx.write_iter(writer, transactions),
+            Codec::Format(format) => format.write_iter(writer, transactions),
+        }
+    }
+
+    /// Returns the number of bytes the transactions take in the format.
+    fn size_of(&self, transactions: &[Transaction]) -> Result<u64, ParserError> {
+        let mut buffer = vec![];
+        match self {
+            Codec::Csv(csv) => csv.write_to(&mut buffer, transactions)?,
+            Codec::FixedWidth(fixed) => fixed.write_to(&mut buffer, transactions)?,
+            Codec::Xml(xml) => xml.write_to(&mut buffer, transactions)?,
+            Codec::Bin(bin) => bin.write_to(&mut buffer, transactions)?,
+            #[cfg(feature = "parquet")]
+            Codec::Parquet(parquet) => parquet.write_to(&mut buffer, transactions)?,
+            #[cfg(feature = "avro")]
+            Codec::Avro(avro) => avro.write_to(&mut buffer, transactions)?,
+            #[cfg(feature = "sqlite")]
+            Codec::Sqlite(sqlite) => sqlite.write_to(&mut buffer, transactions)?,
+            #[cfg(feature = "xlsx")]
+            Codec::Xlsx(xlsx) => xlsx.write_to(&mut buffer, transactions)?,
+            Codec::Format(format) => format.write_to(&mut buffer, transactions)?,
+        }
+        Ok(buffer.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::BinReader;
+    use std::fs::File;
+
+    fn sample(name: &str) -> Input {
+        Input {
+            path: PathBuf::from("../samples").join(name),
+            format: Format::from_path(Path::new(name)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn it_reports_conversion_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("records.csv");
+
+        let stats = convert(
+            &[sample("records_example.bin")],
+            output.to_str().unwrap(),
+            Format::Csv,
+            &ConvertOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.read, 1000);
+        assert_eq!(
+            stats.bytes_in,
+            std::fs::metadata("../samples/records_example.bin")
+                .unwrap()
+                .len()
+        );
+        assert_eq!(stats.bytes_out, std::fs::metadata(&output).unwrap().len());
+
+        let options = ConvertOptions {
+            dry_run: true,
+            ..ConvertOptions::default()
+        };
+        let output = dir.path().join("dry_run.csv");
+        let stats = convert(
+            &[sample("records_example.bin")],
+            output.to_str().unwrap(),
+            Format::Csv,
+            &options,
+        )
+        .unwrap();
+        assert_eq!((stats.read, stats.bytes_out), (1000, 0));
+        assert!(!output.exists());
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn it_leaves_no_output_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut csv = std::fs::read_to_string("../samples/records_example.csv").unwrap();
+        csv.push_str("not,a,transaction\n");
+        let input = dir.path().join("input.csv");
+        std::fs::write(&input, csv).unwrap();
+        let input = Input {
+            path: input,
+            format: Format::Csv,
+        };
+
+        let output = dir.path().join("output.bin");
+        assert!(
+            convert(
+                &[input],
+                output.to_str().unwrap(),
+                Format::Bin,
+                &ConvertOptions::default()
+            )
+            .is_err()
+        );
+        assert_eq!(files(dir.path()), ["input.csv"]);
+    }
+
+    #[test]
+    fn it_does_not_overwrite_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ConvertOptions {
+            overwrite: false,
+            ..ConvertOptions::default()
+        };
+        let output = dir.path().join("records.csv");
+        std::fs::write(&output, "existing").unwrap();
+        assert!(
+            convert(
+                &[sample("records_example.bin")],
+                output.to_str().unwrap(),
+                Format::Csv,
+                &options
+            )
+            .is_err()
+        );
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "existing");
+
+        // The third part exists, so none of the parts is written.
+        std::fs::remove_file(&output).unwrap();
+        std::fs::write(dir.path().join("part-3.csv"), "existing").unwrap();
+        let options = ConvertOptions {
+            split: Some(Split::Records(400)),
+            ..options
+        };
+        let template = dir.path().join("part-{n}.csv");
+        assert!(
+            convert(
+                &[sample("records_example.bin")],
+                template.to_str().unwrap(),
+                Format::Csv,
+                &options
+            )
+            .is_err()
+        );
+        assert_eq!(files(dir.path()), ["part-3.csv"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("part-3.csv")).unwrap(),
+            "existing"
+        );
+    }
+
+    #[test]
+    fn it_removes_stale_bin_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("records.bin");
+        let convert_to = |bin_index| {
+            let options = ConvertOptions {
+                bin_version: BinVersion::V2,
+                bin_index,
+                ..ConvertOptions::default()
+            };
+            convert(
+                &[sample("records_example.csv")],
+                output.to_str().unwrap(),
+                Format::Bin,
+                &options,
+            )
+            .unwrap();
+        };
+
+        convert_to(true);
+        assert_eq!(files(dir.path()), ["records.bin", "records.bin.idx"]);
+        let index = BinIndex::open_sidecar(&output).unwrap();
+        let mut reader = BinReader::with_index(File::open(&output).unwrap(), index).unwrap();
+        assert_eq!(reader.header().unwrap().record_count, Some(1000));
+        assert!(reader.seek_to_position(999).unwrap());
+
+        convert_to(false);
+        assert_eq!(files(dir.path()), ["records.bin"]);
+    }
 
-    let input_parser = input_parser.create_parser();
-    let output_parser = output_parser.create_parser();
+    #[test]
+    fn it_splits_by_bytes_only_uncompressed_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ConvertOptions {
+            split: Some(Split::Bytes(4096)),
+            ..ConvertOptions::default()
+        };
+        let split = |name: &str, format| {
+            convert(
+                &[sample("records_example.bin")],
+                dir.path().join(name).to_str().unwrap(),
+                format,
+                &options,
+            )
+        };
 
-    let transactions = input_parser.read_from(&mut input)?;
-    output_parser.write_to(&mut output, &transactions)?;
+        assert!(split("part-{n}.csv.gz", Format::Csv).is_err());
+        #[cfg(feature = "sqlite")]
+        assert!(split("part-{n}.sqlite", Format::Sqlite).is_err());
+        assert!(files(dir.path()).is_empty());
 
-    Ok(())
+        split("part-{n}.csv", Format::Csv).unwrap();
+        for file in files(dir.path()) {
+            assert!(std::fs::metadata(dir.path().join(file)).unwrap().len() <= 4096);
+        }
+    }
 }
@@ -1,56 +1,427 @@
 use clap::Parser;
-use converter::convert;
-use parser::{BinParserFactory, CsvParserFactory, TxtParserFactory};
-use serde::Serialize;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use converter::{
+    BuiltinTransform, ConvertOptions, DEFAULT_SORT_BUFFER, Input, SortKey, Split, Transform,
+    convert, convert_dir, verify,
+};
+#[cfg(feature = "avro")]
+use parser::AvroCodec;
+#[cfg(feature = "sqlite")]
+use parser::SqliteParser;
+#[cfg(feature = "xlsx")]
+use parser::XlsxSheets;
+use parser::{
+    BinHeader, BinVersion, Compression, CsvColumns, Filter, FixedWidthLayout, Format, XmlParser,
+    XmlStyle,
+};
+#[cfg(feature = "parquet")]
+use parser::{ParquetCompression, ParquetParser};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
-#[derive(clap::ValueEnum, Clone, Debug, Serialize)]
-enum Format {
-    Csv,
-    Txt,
-    Bin,
+fn format_parser() -> impl TypedValueParser<Value = Format> {
+    PossibleValuesParser::new(Format::NAMES).map(|s| s.parse::<Format>().unwrap())
+}
+
+/// Parses CSV columns given inline or, with an `@` prefix, in a mapping file.
+fn parse_columns(s: &str) -> Result<CsvColumns, String> {
+    let columns = match s.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?,
+        None => s.to_string(),
+    };
+    columns.parse::<CsvColumns>().map_err(|e| e.to_string())
+}
+
+/// Parses a fixed-width layout from a file.
+fn parse_layout(path: &str) -> Result<FixedWidthLayout, String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: {}", path, e))?
+        .parse::<FixedWidthLayout>()
+        .map_err(|e| e.to_string())
+}
+
+fn parse_extension_mapping(s: &str) -> Result<(String, Format), String> {
+    let (extension, format) = s
+        .split_once('=')
+        .ok_or(format!("Expected <EXTENSION>=<FORMAT>, found {}", s))?;
+    let format = format.parse::<Format>().map_err(|e| e.to_string())?;
+    Ok((
+        extension.trim_start_matches('.').to_ascii_lowercase(),
+        format,
+    ))
 }
 
 #[derive(clap::Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Input file or glob pattern, may be repeated; inputs are merged in order.
+    #[arg(long, required_unless_present_any = ["input_dir", "xsd"])]
+    input: Vec<String>,
+    /// Output file, or a template for output file names when splitting.
+    #[arg(long, required_unless_present_any = ["output_dir", "xsd"])]
+    output: Option<String>,
+    /// Directory to convert recursively, file by file and in parallel.
+    #[arg(long, requires = "output_dir", conflicts_with_all = ["input", "output", "split"])]
+    input_dir: Option<PathBuf>,
+    /// Directory for the converted files, keeping their paths relative to the input directory.
+    #[arg(long, requires_all = ["input_dir", "output_format"])]
+    output_dir: Option<PathBuf>,
+    /// Format for files with the given extension in the input directory, e.g. 'dat=bin'.
+    #[arg(long = "map-extension", value_parser = parse_extension_mapping)]
+    extension_mappings: Vec<(String, Format)>,
+    /// Number of files converted in parallel; defaults to the number of CPUs.
+    #[arg(long)]
+    jobs: Option<usize>,
+    /// Read and validate the inputs without writing any output.
+    #[arg(long)]
+    dry_run: bool,
+    /// Fail instead of replacing output files that already exist.
+    #[arg(long)]
+    no_overwrite: bool,
+    /// Format of all inputs; detected from the file extensions when omitted.
+    #[arg(long, value_parser = format_parser())]
+    input_format: Option<Format>,
+    /// Format of the output; detected from the file extension when omitted.
+    #[arg(long, value_parser = format_parser())]
+    output_format: Option<Format>,
+    /// Filter expression, e.g. 'tx_type == TRANSFER && amount > 1000'.
+    #[arg(long = "where")]
+    filter: Option<Filter>,
+    /// Transform applied to every transaction, may be repeated: 'mask_user_ids[:<DIGITS>]',
+    /// 'scale_amount:<FACTOR>[/<DIVISOR>]', 'timestamp_unit:<FROM>:<TO>' (s, ms, us, ns),
+    /// 'trim_description', 'upper_description', 'lower_description', 'remap_status:<FROM>=<TO>'.
+    #[arg(long)]
+    transform: Vec<BuiltinTransform>,
+    /// Fields to sort by, e.g. 'amount:desc,tx_id'.
+    #[arg(long, value_delimiter = ',')]
+    sort_by: Vec<SortKey>,
+    /// Number of transactions sorted, or split by date or type, in memory before spilling to
+    /// disk.
+    #[arg(long, default_value_t = DEFAULT_SORT_BUFFER)]
+    sort_buffer: usize,
+    /// Split the output by 'records:<N>', 'bytes:<N>', 'date' or 'tx_type',
+    /// naming files with '{n}', '{date}' or '{tx_type}' in the output template.
+    #[arg(long)]
+    split: Option<Split>,
+    /// Columns of the CSV output as 'field[=name],...', e.g. 'tx_id,amount,from_user_id=payer',
+    /// or '@<FILE>' to read them from a mapping file.
+    #[arg(long, value_parser = parse_columns)]
+    columns: Option<CsvColumns>,
+    /// Columns of CSV inputs, in the same form as '--columns', to read projected files back.
+    #[arg(long, value_parser = parse_columns)]
+    input_columns: Option<CsvColumns>,
+    /// File with the layout of the fixed-width output, one field per line as
+    /// '<name> <offset> <length> [field=<FIELD>] [padding=<PAD>] [format=<FORMAT>] [decimals=<N>]'.
+    #[arg(long, value_parser = parse_layout)]
+    layout: Option<FixedWidthLayout>,
+    /// File with the layout of fixed-width inputs, in the same form as '--layout'.
+    #[arg(long, value_parser = parse_layout)]
+    input_layout: Option<FixedWidthLayout>,
+    /// How fields are written to XML outputs: 'elements' or 'attributes'.
+    #[arg(long, default_value_t = XmlStyle::default())]
+    xml_style: XmlStyle,
+    /// Version of BIN outputs: '1' (the default), or '2' for a file header and trailer; BIN
+    /// inputs of either version are converted when it is given.
+    #[arg(long)]
+    bin_version: Option<BinVersion>,
+    /// Write a CRC32C of every record and a SHA-256 of the file to BIN outputs, which are BIN v2
+    /// unless '--bin-version' says otherwise.
     #[arg(long)]
-    input: String,
+    bin_checksums: bool,
+    /// Write an index of BIN outputs to a sidecar, e.g. 'records.bin.idx', to look records up by
+    /// ID, position or time range without reading the whole file.
     #[arg(long)]
-    output: String,
-    #[arg(long, value_enum)]
-    input_format: Format,
-    #[arg(long, value_enum)]
-    output_format: Format,
+    bin_index: bool,
+    /// Maximum number of transactions in a row group of Parquet outputs.
+    #[cfg(feature = "parquet")]
+    #[arg(long, default_value_t = ParquetParser::DEFAULT_ROW_GROUP_SIZE)]
+    parquet_row_group_size: usize,
+    /// Compression of Parquet columns: 'uncompressed', 'snappy', 'gzip' or 'zstd'.
+    #[cfg(feature = "parquet")]
+    #[arg(long, default_value_t = ParquetCompression::default())]
+    parquet_compression: ParquetCompression,
+    /// Compression of Avro blocks: 'null', 'deflate', 'snappy' or 'zstd'.
+    #[cfg(feature = "avro")]
+    #[arg(long, default_value_t = AvroCodec::default())]
+    avro_codec: AvroCodec,
+    /// Table of SQLite databases that transactions are written to and read from.
+    #[cfg(feature = "sqlite")]
+    #[arg(long, default_value = SqliteParser::DEFAULT_TABLE)]
+    sqlite_table: String,
+    /// SQL query whose rows are read from SQLite inputs instead of the whole table.
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    sqlite_query: Option<String>,
+    /// Sheets of XLSX outputs: 'single', or 'per-type' for one sheet per transaction type.
+    #[cfg(feature = "xlsx")]
+    #[arg(long, default_value_t = XlsxSheets::default())]
+    xlsx_sheets: XlsxSheets,
+    /// Write the XML Schema of XML outputs, in the selected style, to a file.
+    #[arg(long)]
+    xsd: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Read every transaction of a file, verifying the checksums of BIN files, without converting it.
+    Verify {
+        /// File to verify.
+        file: PathBuf,
+        /// Format of the file; detected from the file extension when omitted.
+        #[arg(long, value_parser = format_parser())]
+        format: Option<Format>,
+    },
+}
+
+/// Expands input arguments into paths, resolving glob patterns.
+fn expand_inputs(patterns: &[String]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut paths = vec![];
+    for pattern in patterns {
+        if !pattern.contains(['*', '?', '[']) {
+            paths.push(PathBuf::from(pattern));
+            continue;
+        }
+        let matched = glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?;
+        if matched.is_empty() {
+            return Err(format!("No files match {}", pattern).into());
+        }
+        paths.extend(matched);
+    }
+    Ok(paths)
+}
+
+fn detect_format(path: &Path, format: Option<Format>) -> Result<Format, String> {
+    format
+        .or_else(|| Format::from_path(path))
+        .ok_or(format!("Cannot detect format of {}", path.display()))
+}
+
+/// Returns whether an output is compressed with another codec than its input, whose codec is
+/// detected from its content.
+fn is_recompressed(input: &Path, output: &Path) -> std::io::Result<bool> {
+    let mut input = BufReader::new(File::open(input)?);
+    Ok(Compression::detect(&mut input)? != Compression::from_path(output))
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    run(Args::parse())
+}
 
-    match (args.input_format, args.output_format) {
-        (Format::Csv, Format::Txt) => {
-            convert(args.input, args.output, CsvParserFactory, TxtParserFactory)
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(Command::Verify { file, format }) = &args.command {
+        let format = detect_format(file, *format)?;
+        let verification = verify(&Input {
+            path: file.clone(),
+            format,
+        })?;
+        if let Some(header) = &verification.bin_header {
+            let checksums = match (
+                header.flags & BinHeader::RECORD_CRC32C != 0,
+                header.flags & BinHeader::FILE_SHA256 != 0,
+            ) {
+                (true, true) => "record CRC32C and file SHA-256 checksums",
+                (true, false) => "record CRC32C checksums",
+                (false, true) => "a file SHA-256 checksum",
+                (false, false) => "no checksums",
+            };
+            println!(
+                "BIN v{} written by {} at {} with {}.",
+                header.version, header.producer, header.created, checksums
+            );
         }
-        (Format::Txt, Format::Csv) => {
-            convert(args.input, args.output, TxtParserFactory, CsvParserFactory)
-        },
-        (Format::Csv, Format::Bin) => {
-            convert(args.input, args.output, CsvParserFactory, BinParserFactory)
-        },
-        (Format::Txt, Format::Bin) => {
-            convert(args.input, args.output, TxtParserFactory, BinParserFactory)
-        },
-        (Format::Bin, Format::Csv) => {
-            convert(args.input, args.output, BinParserFactory, CsvParserFactory)
-        },
-        (Format::Bin, Format::Txt) => {
-            convert(args.input, args.output, BinParserFactory, TxtParserFactory)
-        },
-        _ => {
-            println!("Conversion is not needed. Format is the same.");
+        println!(
+            "{} is valid: {} records.",
+            file.display(),
+            verification.records
+        );
+        return Ok(());
+    }
+
+    if let Some(xsd) = &args.xsd {
+        std::fs::write(xsd, XmlParser::new(args.xml_style).xsd())?;
+        println!("XML Schema was written to {}.", xsd.display());
+        if args.input.is_empty() && args.input_dir.is_none() {
             return Ok(());
         }
-    }?;
+    }
+
+    let options = ConvertOptions {
+        filter: args.filter,
+        transforms: args
+            .transform
+            .into_iter()
+            .map(|transform| Box::new(transform) as Box<dyn Transform + Send + Sync>)
+            .collect(),
+        sort_by: args.sort_by,
+        sort_buffer: args.sort_buffer,
+        split: args.split,
+        input_columns: args.input_columns,
+        output_columns: args.columns,
+        input_layout: args.input_layout,
+        output_layout: args.layout,
+        xml_style: args.xml_style,
+        bin_version: args.bin_version.unwrap_or(if args.bin_checksums {
+            BinVersion::V2
+        } else {
+            BinVersion::default()
+        }),
+        bin_checksums: args.bin_checksums,
+        bin_index: args.bin_index,
+        #[cfg(feature = "parquet")]
+        parquet_row_group_size: args.parquet_row_group_size,
+        #[cfg(feature = "parquet")]
+        parquet_compression: args.parquet_compression,
+        #[cfg(feature = "avro")]
+        avro_codec: args.avro_codec,
+        #[cfg(feature = "sqlite")]
+        sqlite_table: args.sqlite_table,
+        #[cfg(feature = "sqlite")]
+        sqlite_query: args.sqlite_query,
+        #[cfg(feature = "xlsx")]
+        xlsx_sheets: args.xlsx_sheets,
+        dry_run: args.dry_run,
+        overwrite: !args.no_overwrite,
+    };
+
+    if let (Some(input_dir), Some(output_dir)) = (&args.input_dir, &args.output_dir) {
+        if let Some(jobs) = args.jobs {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build_global()?;
+        }
+
+        let mappings = args
+            .extension_mappings
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let detect = |path: &Path| {
+            args.input_format.or_else(|| {
+                let path = Compression::strip_extension(path);
+                let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+                mappings
+                    .get(&extension)
+                    .copied()
+                    .or_else(|| Format::from_path(&path))
+            })
+        };
+        let output_format = args.output_format.ok_or("Output format is required")?;
+
+        let summary = convert_dir(input_dir, output_dir, output_format, detect, &options)?;
+
+        for (path, e) in &summary.failed {
+            eprintln!("Failed to convert {}: {}", path.display(), e);
+        }
+        println!(
+            "Converted {} files, failed {}, skipped {}.",
+            summary.converted.len(),
+            summary.failed.len(),
+            summary.skipped.len()
+        );
+        println!("{}", summary.stats);
+
+        if !summary.failed.is_empty() {
+            return Err(format!("{} files failed to convert", summary.failed.len()).into());
+        }
+        return Ok(());
+    }
+
+    let output = args.output.ok_or("Output is required")?;
+    let inputs = expand_inputs(&args.input)?
+        .into_iter()
+        .map(|path| {
+            let format = detect_format(&path, args.input_format)?;
+            Ok(Input { path, format })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let output_format = detect_format(Path::new(&output), args.output_format)?;
+
+    #[cfg(feature = "parquet")]
+    let parquet_modified = options.parquet_row_group_size != ParquetParser::DEFAULT_ROW_GROUP_SIZE
+        || options.parquet_compression != ParquetCompression::default();
+    #[cfg(not(feature = "parquet"))]
+    let parquet_modified = false;
+    #[cfg(feature = "avro")]
+    let avro_modified = options.avro_codec != AvroCodec::default();
+    #[cfg(not(feature = "avro"))]
+    let avro_modified = false;
+    #[cfg(feature = "sqlite")]
+    let sqlite_modified =
+        options.sqlite_table != SqliteParser::DEFAULT_TABLE || options.sqlite_query.is_some();
+    #[cfg(not(feature = "sqlite"))]
+    let sqlite_modified = false;
+    #[cfg(feature = "xlsx")]
+    let xlsx_modified = options.xlsx_sheets != XlsxSheets::default();
+    #[cfg(not(feature = "xlsx"))]
+    let xlsx_modified = false;
+
+    let modified = options.filter.is_some()
+        || !options.transforms.is_empty()
+        || !options.sort_by.is_empty()
+        || options.split.is_some()
+        || options.input_columns.is_some()
+        || options.output_columns.is_some()
+        || options.input_layout.is_some()
+        || options.output_layout.is_some()
+        || options.xml_style != XmlStyle::default()
+        || args.bin_version.is_some()
+        || args.bin_checksums
+        || options.bin_index
+        || inputs.len() > 1
+        || parquet_modified
+        || avro_modified
+        || sqlite_modified
+        || xlsx_modified
+        || is_recompressed(&inputs[0].path, Path::new(&output))?;
+
+    if !modified && !options.dry_run && inputs[0].format == output_format {
+        println!("Conversion is not needed. Format is the same.");
+        return Ok(());
+    }
+
+    let stats = convert(&inputs, &output, output_format, &options)?;
 
-    println!("Conversion was done successfully.");
+    if options.dry_run {
+        println!("Dry run was done successfully, nothing was written.");
+    } else {
+        println!("Conversion was done successfully.");
+    }
+    println!("{}", stats);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_compresses_and_decompresses_the_same_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let compressed = dir.path().join("records.csv.gz");
+        let decompressed = dir.path().join("records.csv");
+        let convert = |input: &Path, output: &Path| {
+            run(Args::parse_from([
+                "converter",
+                "--input",
+                input.to_str().unwrap(),
+                "--output",
+                output.to_str().unwrap(),
+            ]))
+            .unwrap();
+        };
+
+        convert(Path::new("../samples/records_example.csv"), &compressed);
+        let mut file = BufReader::new(File::open(&compressed).unwrap());
+        assert_eq!(Compression::detect(&mut file).unwrap(), Compression::Gzip);
+
+        convert(&compressed, &decompressed);
+        assert_eq!(
+            std::fs::read(&decompressed).unwrap(),
+            std::fs::read("../samples/records_example.csv").unwrap()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! The `sort` module provides ordering of transactions by one or more fields.
+//!
+//! Transactions are sorted in memory while they fit into the sort buffer. Larger inputs
+//! are sorted with an external merge sort: every full buffer is sorted and spilled to a
+//! temporary BIN file, and the spilled runs are merged while the output is written.
+
+use parser::{
+    BinParserFactory, Field, Parser, ParserError, ParserFactory, Transaction, Transactions,
+};
+use std::cmp::Ordering;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+/// The default number of transactions sorted in memory before spilling to disk.
+pub const DEFAULT_SORT_BUFFER: usize = 1_000_000;
+
+/// The `SortOrder` enum represents the direction of a sort key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Ascending order.
+    Asc,
+    /// Descending order.
+    Desc,
+}
+
+/// The `SortKey` struct is a field to sort by together with its direction.
+///
+/// Sort keys are parsed from `field` or `field:asc|desc`, e.g. `amount:desc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    /// The field to sort by.
+    pub field: Field,
+    /// The direction of the sort.
+    pub order: SortOrder,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, order) = s.split_once(':').unwrap_or((s, "asc"));
+        let field = field.trim().parse::<Field>().map_err(|e| e.to_string())?;
+        let order = match order.trim().to_ascii_lowercase().as_str() {
+            "asc" => SortOrder::Asc,
+            "desc" => SortOrder::Desc,
+            _ => return Err(format!("Unknown sort order: {}", order)),
+        };
+        Ok(Self { field, order })
+    }
+}
+
+/// Compares two transactions by the given sort keys.
+pub fn compare(keys: &[SortKey], a: &Transaction, b: &Transaction) -> Ordering {
+    keys.iter()
+        .map(|key| match key.order {
+            SortOrder::Asc => key.field.compare(a, b),
+            SortOrder::Desc => key.field.compare(b, a),
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Sorts transactions by the given keys, keeping at most `buffer_size` of them in memory.
+///
+/// The sort is stable: transactions with equal keys keep their input order.
+pub fn sort<'a>(
+    transactions: impl Iterator<Item = Result<Transaction, ParserError>>,
+    keys: &'a [SortKey],
+    buffer_size: usize,
+) -> Result<Transactions<'a>, ParserError> {
+    let buffer_size = buffer_size.max(1);
+    let mut runs = vec![];
+    let mut buffer = vec![];
+
+    for transaction in transactions {
+        buffer.push(transaction?);
+        if buffer.len() >= buffer_size {
+            runs.push(spill(&mut buffer, keys)?);
+        }
+    }
+
+    if runs.is_empty() {
+        buffer.sort_by(|a, b| compare(keys, a, b));
+        return Ok(Box::new(buffer.into_iter().map(Ok)));
+    }
+
+    if !buffer.is_empty() {
+        runs.push(spill(&mut buffer, keys)?);
+    }
+
+    Ok(Box::new(Merge::new(runs, keys)?))
+}
+
+/// Sorts the buffer, writes it to a temporary file and returns a reader over the file.
+fn spill(
+    buffer: &mut Vec<Transaction>,
+    keys: &[SortKey],
+) -> Result<Transactions<'static>, ParserError> {
+    buffer.sort_by(|a, b| compare(keys, a, b));
+
+    let parser = BinParserFactory.create_parser();
+    let mut file = tempfile::tempfile()?;
+    let mut writer = BufWriter::new(&mut file);
+    parser.write_to(&mut writer, buffer)?;
+    writer.flush()?;
+    drop(writer);
+    file.seek(SeekFrom::Start(0))?;
+
+    buffer.clear();
+
+    Ok(parser.read_iter(BufReader::new(file)))
+}
+
+/// The `Merge` struct merges sorted runs into a single sorted sequence.
+struct Merge<'a> {
+    runs: Vec<Transactions<'static>>,
+    heads: Vec<Option<Transaction>>,
+    keys: &'a [SortKey],
+}
+
+impl<'a> Merge<'a> {
+    fn new(mut runs: Vec<Transactions<'static>>, keys: &'a [SortKey]) -> Result<Self, ParserError> {
+        let heads = runs
+            .iter_mut()
+            .map(|run| run.next().transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { runs, heads, keys })
+    }
+}
+
+impl Iterator for Merge<'_> {
+    type Item = Result<Transaction, ParserError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut min: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            let Some(head) = head else { continue };
+            let is_less = match min.and_then(|m| self.heads[m].as_ref()) {
+                Some(current) => compare(self.keys, head, current).is_lt(),
+                None => true,
+            };
+            if is_less {
+                min = Some(i);
+            }
+        }
+
+        let i = min?;
+        let next = match self.runs[i].next().transpose() {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e)),
+        };
+        std::mem::replace(&mut self.heads[i], next).map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::CsvParserFactory;
+    use std::fs::File;
+
+    fn read_sample() -> Vec<Transaction> {
+        CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn it_parses_sort_keys() {
+        assert_eq!(
+            "amount:DESC".parse::<SortKey>(),
+            Ok(SortKey {
+                field: Field::Amount,
+                order: SortOrder::Desc
+            })
+        );
+        assert_eq!(
+            "TX_ID".parse::<SortKey>(),
+            Ok(SortKey {
+                field: Field::TxId,
+                order: SortOrder::Asc
+            })
+        );
+        assert!("amount:up".parse::<SortKey>().is_err());
+        assert!("unknown".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn it_sorts_externally_like_in_memory() {
+        let keys = [
+            "status".parse::<SortKey>().unwrap(),
+            "amount:desc".parse::<SortKey>().unwrap(),
+        ];
+        let transactions = read_sample();
+
+        let in_memory = sort(transactions.clone().into_iter().map(Ok), &keys, usize::MAX)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let external = sort(transactions.into_iter().map(Ok), &keys, 64)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(in_memory.len(), 1000);
+        assert!(
+            in_memory
+                .windows(2)
+                .all(|w| compare(&keys, &w[0], &w[1]).is_le())
+        );
+        assert_eq!(in_memory, external);
+    }
+}
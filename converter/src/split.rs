@@ -0,0 +1,249 @@
+//! The `split` module provides splitting of the converter output into several files.
+//!
+//! Output file names are produced from a template with placeholders:
+//! - `{n}`: the 1-based part number, used when splitting by records or bytes.
+//! - `{date}`: the `YYYY-MM-DD` date (UTC) of the `timestamp` in milliseconds.
+//! - `{tx_type}`: the transaction type, e.g. `TRANSFER`.
+//!
+//! Splitting by date or type keeps a bounded number of transactions in memory and spills the
+//! rest to a temporary BIN file per part, which is read back when the part is written.
+
+use parser::{BinParserFactory, Parser, ParserError, ParserFactory, Transaction, format_iso_date};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::str::FromStr;
+use tempfile::TempPath;
+
+/// The `Split` enum represents a rule for splitting output into several files.
+///
+/// Splits are parsed from `records:<N>`, `bytes:<N>`, `date` or `tx_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// At most the given number of transactions per file.
+    Records(usize),
+    /// At most the given number of bytes per file, unless a single transaction is larger, for
+    /// uncompressed outputs whose size is the sum of the sizes of their transactions.
+    Bytes(u64),
+    /// One file per day of the transaction timestamp.
+    Date,
+    /// One file per transaction type.
+    TxType,
+}
+
+impl FromStr for Split {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (s, None),
+        };
+        let parse_limit = |value: Option<&str>| -> Result<u64, String> {
+            value
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .filter(|limit| *limit > 0)
+                .ok_or(format!("Expected a positive limit in split: {}", s))
+        };
+        match (kind.trim().to_ascii_lowercase().as_str(), value) {
+            ("records", value) => Ok(Split::Records(parse_limit(value)? as usize)),
+            ("bytes", value) => Ok(Split::Bytes(parse_limit(value)?)),
+            ("date", None) => Ok(Split::Date),
+            ("tx_type", None) => Ok(Split::TxType),
+            _ => Err(format!("Unknown split: {}", s)),
+        }
+    }
+}
+
+impl Split {
+    /// The template placeholder that distinguishes the output files.
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Split::Records(_) | Split::Bytes(_) => "{n}",
+            Split::Date => "{date}",
+            Split::TxType => "{tx_type}",
+        }
+    }
+
+    /// Splits transactions into parts and passes each part with its file name to `write`.
+    ///
+    /// `size_of` returns the number of bytes that transactions take in the output. When
+    /// splitting by date or type, at most `buffer_size` transactions are kept in memory.
+    pub(crate) fn write<I, F>(
+        &self,
+        template: &str,
+        size_of: impl Fn(&[Transaction]) -> Result<u64, ParserError>,
+        buffer_size: usize,
+        transactions: I,
+        mut write: F,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        I: Iterator<Item = Result<Transaction, ParserError>>,
+        F: FnMut(
+            &str,
+            &mut dyn Iterator<Item = Result<Transaction, ParserError>>,
+        ) -> Result<(), Box<dyn std::error::Error>>,
+    {
+        if !template.contains(self.placeholder()) {
+            return Err(format!(
+                "Output name {} must contain {} to split the output",
+                template,
+                self.placeholder()
+            )
+            .into());
+        }
+
+        match *self {
+            Split::Records(limit) => {
+                let mut transactions = transactions.peekable();
+                let mut n = 1;
+                while transactions.peek().is_some() {
+                    let name = template.replace("{n}", &n.to_string());
+                    write(&name, &mut transactions.by_ref().take(limit))?;
+                    n += 1;
+                }
+            }
+            Split::Bytes(limit) => {
+                let header_size = size_of(&[])?;
+                let mut part = vec![];
+                let mut part_size = header_size;
+                let mut n = 1;
+                for transaction in transactions {
+                    let transaction = transaction?;
+                    let size = size_of(std::slice::from_ref(&transaction))? - header_size;
+                    if !part.is_empty() && part_size + size > limit {
+                        let name = template.replace("{n}", &n.to_string());
+                        write(&name, &mut std::mem::take(&mut part).into_iter().map(Ok))?;
+                        part_size = header_size;
+                        n += 1;
+                    }
+                    part.push(transaction);
+                    part_size += size;
+                }
+                if !part.is_empty() {
+                    let name = template.replace("{n}", &n.to_string());
+                    write(&name, &mut part.into_iter().map(Ok))?;
+                }
+            }
+            Split::Date | Split::TxType => {
+                let mut parts: BTreeMap<String, Part> = BTreeMap::new();
+                let mut buffered = 0;
+                for transaction in transactions {
+                    let transaction = transaction?;
+                    let key = match self {
+                        Split::Date => format_iso_date(transaction.timestamp),
+                        _ => transaction.tx_type.to_string(),
+                    };
+                    parts.entry(key).or_default().buffer.push(transaction);
+                    buffered += 1;
+                    if buffered >= buffer_size.max(1) {
+                        for part in parts.values_mut() {
+                            part.spill()?;
+                        }
+                        buffered = 0;
+                    }
+                }
+                let parser = BinParserFactory.create_parser();
+                for (key, Part { spilled, buffer }) in parts {
+                    let name = template.replace(self.placeholder(), &key);
+                    let file = spilled.as_ref().map(File::open).transpose()?;
+                    let mut part = file
+                        .into_iter()
+                        .flat_map(|file| parser.read_iter(BufReader::new(file)))
+                        .chain(buffer.into_iter().map(Ok));
+                    write(&name, &mut part)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The `Part` struct is the transactions of a part that are not yet written.
+#[derive(Default)]
+struct Part {
+    /// A temporary file with the spilled transactions, which come before the buffered ones.
+    spilled: Option<TempPath>,
+    buffer: Vec<Transaction>,
+}
+
+impl Part {
+    /// Appends the buffered transactions to the temporary file of the part.
+    fn spill(&mut self) -> Result<(), ParserError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let file = match &self.spilled {
+            Some(path) => OpenOptions::new().append(true).open(path)?,
+            None => {
+                let (file, path) = tempfile::NamedTempFile::new()?.into_parts();
+                self.spilled = Some(path);
+                file
+            }
+        };
+        let mut writer = BufWriter::new(file);
+        BinParserFactory
+            .create_parser()
+            .write_to(&mut writer, &self.buffer)?;
+        writer.flush()?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::TransactionType;
+
+    #[test]
+    fn it_parses_splits() {
+        assert_eq!("records:100".parse::<Split>(), Ok(Split::Records(100)));
+        assert_eq!("bytes:4096".parse::<Split>(), Ok(Split::Bytes(4096)));
+        assert_eq!("date".parse::<Split>(), Ok(Split::Date));
+        assert_eq!("TX_TYPE".parse::<Split>(), Ok(Split::TxType));
+        assert!("records".parse::<Split>().is_err());
+        assert!("records:0".parse::<Split>().is_err());
+        assert!("date:1".parse::<Split>().is_err());
+        assert!("hour".parse::<Split>().is_err());
+    }
+
+    #[test]
+    fn it_splits_by_tx_type_with_spilling() {
+        let transactions = (0..7).map(|tx_id| Transaction {
+            tx_id,
+            tx_type: if tx_id % 3 == 0 {
+                TransactionType::Deposit
+            } else {
+                TransactionType::Transfer
+            },
+            ..Transaction::default()
+        });
+
+        for buffer_size in [2, 100] {
+            let mut parts = vec![];
+            Split::TxType
+                .write(
+                    "{tx_type}.bin",
+                    |_| Ok(0),
+                    buffer_size,
+                    transactions.clone().map(Ok),
+                    |name, part| {
+                        let ids = part
+                            .map(|transaction| transaction.map(|transaction| transaction.tx_id))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        parts.push((name.to_string(), ids));
+                        Ok(())
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                parts,
+                [
+                    ("DEPOSIT.bin".to_string(), vec![0, 3, 6]),
+                    ("TRANSFER.bin".to_string(), vec![1, 2, 4, 5]),
+                ]
+            );
+        }
+    }
+}
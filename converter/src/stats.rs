@@ -0,0 +1,162 @@
+//! The `stats` module provides statistics about a conversion.
+
+use parser::{Transaction, TransactionStatus, TransactionType};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// The `Stats` struct summarizes the transactions and files of a conversion.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of transactions read from the inputs.
+    pub read: u64,
+    /// The number of transactions converted, after filtering.
+    pub converted: u64,
+    /// The number of converted transactions per type.
+    pub by_type: BTreeMap<TransactionType, u64>,
+    /// The number of converted transactions per status.
+    pub by_status: BTreeMap<TransactionStatus, u64>,
+    /// The total amount of the converted transactions.
+    pub total_amount: u128,
+    /// The earliest timestamp of the converted transactions.
+    pub min_timestamp: Option<u64>,
+    /// The latest timestamp of the converted transactions.
+    pub max_timestamp: Option<u64>,
+    /// The size of the input files in bytes.
+    pub bytes_in: u64,
+    /// The size of the output files in bytes.
+    pub bytes_out: u64,
+    /// The duration of the conversion.
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    /// Adds a converted transaction.
+    pub fn add(&mut self, transaction: &Transaction) {
+        self.converted += 1;
+        *self.by_type.entry(transaction.tx_type).or_default() += 1;
+        *self.by_status.entry(transaction.status).or_default() += 1;
+        self.total_amount += transaction.amount as u128;
+        let timestamp = transaction.timestamp;
+        self.min_timestamp = Some(
+            self.min_timestamp
+                .map_or(timestamp, |min| min.min(timestamp)),
+        );
+        self.max_timestamp = Some(
+            self.max_timestamp
+                .map_or(timestamp, |max| max.max(timestamp)),
+        );
+    }
+
+    /// Adds the statistics of another conversion, except its duration.
+    pub fn merge(&mut self, other: &Stats) {
+        self.read += other.read;
+        self.converted += other.converted;
+        for (tx_type, count) in &other.by_type {
+            *self.by_type.entry(*tx_type).or_default() += count;
+        }
+        for (status, count) in &other.by_status {
+            *self.by_status.entry(*status).or_default() += count;
+        }
+        self.total_amount += other.total_amount;
+        self.min_timestamp = match (self.min_timestamp, other.min_timestamp) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max_timestamp = self.max_timestamp.max(other.max_timestamp);
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |counts: Vec<(String, u64)>| {
+            counts
+                .into_iter()
+                .map(|(key, count)| format!("{} {}", key, count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        writeln!(
+            f,
+            "Records: {} read, {} converted",
+            self.read, self.converted
+        )?;
+        writeln!(
+            f,
+            "By type: {}",
+            counts(
+                self.by_type
+                    .iter()
+                    .map(|(tx_type, count)| (tx_type.to_string(), *count))
+                    .collect()
+            )
+        )?;
+        writeln!(
+            f,
+            "By status: {}",
+            counts(
+                self.by_status
+                    .iter()
+                    .map(|(status, count)| (status.to_string(), *count))
+                    .collect()
+            )
+        )?;
+        writeln!(f, "Total amount: {}", self.total_amount)?;
+        if let (Some(min), Some(max)) = (self.min_timestamp, self.max_timestamp) {
+            writeln!(f, "Timestamps: {} to {}", min, max)?;
+        }
+        writeln!(f, "Bytes: {} in, {} out", self.bytes_in, self.bytes_out)?;
+
+        let seconds = self.elapsed.as_secs_f64();
+        write!(f, "Elapsed: {:.3} s", seconds)?;
+        if seconds > 0.0 {
+            write!(
+                f,
+                " ({:.0} records/s, {:.2} MB/s)",
+                self.read as f64 / seconds,
+                self.bytes_in as f64 / seconds / 1_000_000.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_collects_stats() {
+        let mut stats = Stats::default();
+        stats.add(&Transaction {
+            tx_type: TransactionType::Transfer,
+            amount: 100,
+            timestamp: 20,
+            ..Transaction::default()
+        });
+        stats.add(&Transaction {
+            status: TransactionStatus::Pending,
+            amount: u64::MAX,
+            timestamp: 10,
+            ..Transaction::default()
+        });
+
+        let mut merged = Stats::default();
+        merged.merge(&stats);
+        merged.merge(&Stats::default());
+
+        for stats in [stats, merged] {
+            assert_eq!(stats.converted, 2);
+            assert_eq!(stats.by_type[&TransactionType::Transfer], 1);
+            assert_eq!(stats.by_type[&TransactionType::Deposit], 1);
+            assert_eq!(stats.by_status[&TransactionStatus::Pending], 1);
+            assert_eq!(stats.total_amount, u64::MAX as u128 + 100);
+            assert_eq!(stats.min_timestamp, Some(10));
+            assert_eq!(stats.max_timestamp, Some(20));
+        }
+    }
+}
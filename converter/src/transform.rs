@@ -0,0 +1,264 @@
+//! The `transform` module provides rewriting of transactions during conversion.
+//!
+//! A `Transform` changes a single transaction. Transforms are applied in order, after
+//! filtering and before sorting. Built-in transforms are parsed from specs:
+//! - `mask_user_ids[:<DIGITS>]`: keeps only the last digits of user IDs (4 by default).
+//! - `scale_amount:<FACTOR>` or `scale_amount:<NUMERATOR>/<DENOMINATOR>`: scales amounts.
+//! - `timestamp_unit:<FROM>:<TO>`: converts timestamps between `s`, `ms`, `us` and `ns`.
+//! - `trim_description`, `upper_description`, `lower_description`: rewrites descriptions.
+//! - `remap_status:<FROM>=<TO>`: replaces one transaction status with another.
+
+use parser::{ParserError, Transaction, TransactionStatus};
+use std::str::FromStr;
+
+/// The `Transform` trait represents a rewrite of a transaction.
+pub trait Transform {
+    /// Applies the transform to a transaction.
+    fn apply(&self, transaction: Transaction) -> Result<Transaction, ParserError>;
+}
+
+/// The `TimestampUnit` enum represents the unit of a timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampUnit {
+    /// Seconds.
+    Seconds,
+    /// Milliseconds.
+    Milliseconds,
+    /// Microseconds.
+    Microseconds,
+    /// Nanoseconds.
+    Nanoseconds,
+}
+
+impl FromStr for TimestampUnit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "s" => Ok(TimestampUnit::Seconds),
+            "ms" => Ok(TimestampUnit::Milliseconds),
+            "us" => Ok(TimestampUnit::Microseconds),
+            "ns" => Ok(TimestampUnit::Nanoseconds),
+            _ => Err(format!("Unknown timestamp unit: {}", s)),
+        }
+    }
+}
+
+impl TimestampUnit {
+    fn per_second(&self) -> u64 {
+        match self {
+            TimestampUnit::Seconds => 1,
+            TimestampUnit::Milliseconds => 1_000,
+            TimestampUnit::Microseconds => 1_000_000,
+            TimestampUnit::Nanoseconds => 1_000_000_000,
+        }
+    }
+}
+
+/// The `BuiltinTransform` enum represents the transforms available from the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuiltinTransform {
+    /// Keeps only the given number of last decimal digits of user IDs.
+    MaskUserIds(u32),
+    /// Multiplies amounts by a numerator and divides them by a denominator.
+    ScaleAmount(u64, u64),
+    /// Converts timestamps from one unit to another.
+    TimestampUnit(TimestampUnit, TimestampUnit),
+    /// Removes leading and trailing whitespace from descriptions.
+    TrimDescription,
+    /// Converts descriptions to upper case.
+    UpperDescription,
+    /// Converts descriptions to lower case.
+    LowerDescription,
+    /// Replaces one transaction status with another.
+    RemapStatus(TransactionStatus, TransactionStatus),
+}
+
+impl FromStr for BuiltinTransform {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = match s.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (s, None),
+        };
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("Expected a number in transform: {}", s))
+        };
+
+        match (name.trim(), argument) {
+            ("mask_user_ids", None) => Ok(BuiltinTransform::MaskUserIds(4)),
+            ("mask_user_ids", Some(digits)) => {
+                Ok(BuiltinTransform::MaskUserIds(number(digits)?.min(20) as u32))
+            }
+            ("scale_amount", Some(factor)) => {
+                let (numerator, denominator) = factor.split_once('/').unwrap_or((factor, "1"));
+                let denominator = number(denominator)?;
+                if denominator == 0 {
+                    return Err(format!("Division by zero in transform: {}", s));
+                }
+                Ok(BuiltinTransform::ScaleAmount(
+                    number(numerator)?,
+                    denominator,
+                ))
+            }
+            ("timestamp_unit", Some(units)) => {
+                let (from, to) = units
+                    .split_once(':')
+                    .ok_or(format!("Expected <FROM>:<TO> units in transform: {}", s))?;
+                Ok(BuiltinTransform::TimestampUnit(from.parse()?, to.parse()?))
+            }
+            ("trim_description", None) => Ok(BuiltinTransform::TrimDescription),
+            ("upper_description", None) => Ok(BuiltinTransform::UpperDescription),
+            ("lower_description", None) => Ok(BuiltinTransform::LowerDescription),
+            ("remap_status", Some(statuses)) => {
+                let (from, to) = statuses
+                    .split_once('=')
+                    .ok_or(format!("Expected <FROM>=<TO> statuses in transform: {}", s))?;
+                let status = |value: &str| {
+                    value
+                        .trim()
+                        .to_ascii_uppercase()
+                        .parse::<TransactionStatus>()
+                        .map_err(|e| e.to_string())
+                };
+                Ok(BuiltinTransform::RemapStatus(status(from)?, status(to)?))
+            }
+            _ => Err(format!("Unknown transform: {}", s)),
+        }
+    }
+}
+
+impl Transform for BuiltinTransform {
+    fn apply(&self, mut transaction: Transaction) -> Result<Transaction, ParserError> {
+        match self {
+            BuiltinTransform::MaskUserIds(digits) => {
+                // 20 digits or more keep every ID, as none is longer than 20 digits.
+                if let Some(modulus) = 10u64.checked_pow(*digits) {
+                    transaction.from_user_id %= modulus;
+                    transaction.to_user_id %= modulus;
+                }
+            }
+            BuiltinTransform::ScaleAmount(numerator, denominator) => {
+                let amount = transaction.amount as u128 * *numerator as u128 / *denominator as u128;
+                transaction.amount = u64::try_from(amount).map_err(|_| {
+                    ParserError::InvalidValue(format!(
+                        "AMOUNT overflows after scaling: {}",
+                        transaction.amount
+                    ))
+                })?;
+            }
+            BuiltinTransform::TimestampUnit(from, to) => {
+                let timestamp = transaction.timestamp as u128 * to.per_second() as u128
+                    / from.per_second() as u128;
+                transaction.timestamp = u64::try_from(timestamp).map_err(|_| {
+                    ParserError::InvalidValue(format!(
+                        "TIMESTAMP overflows after conversion: {}",
+                        transaction.timestamp
+                    ))
+                })?;
+            }
+            BuiltinTransform::TrimDescription => {
+                transaction.description = transaction.description.trim().to_string();
+            }
+            BuiltinTransform::UpperDescription => {
+                transaction.description = transaction.description.to_uppercase();
+            }
+            BuiltinTransform::LowerDescription => {
+                transaction.description = transaction.description.to_lowercase();
+            }
+            BuiltinTransform::RemapStatus(from, to) => {
+                if transaction.status == *from {
+                    transaction.status = *to;
+                }
+            }
+        }
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::TransactionType;
+
+    fn apply(specs: &[&str], transaction: Transaction) -> Result<Transaction, ParserError> {
+        specs.iter().try_fold(transaction, |transaction, spec| {
+            spec.parse::<BuiltinTransform>().unwrap().apply(transaction)
+        })
+    }
+
+    #[test]
+    fn it_applies_transforms_in_order() {
+        let transaction = Transaction::new(
+            1,
+            TransactionType::Transfer,
+            9223372036854775807,
+            42,
+            1050,
+            1633036860123,
+            TransactionStatus::Pending,
+            "  Record one ".to_string(),
+        );
+        let transformed = apply(
+            &[
+                "mask_user_ids",
+                "scale_amount:3/2",
+                "timestamp_unit:ms:s",
+                "trim_description",
+                "upper_description",
+                "remap_status:pending=FAILURE",
+            ],
+            transaction,
+        )
+        .unwrap();
+
+        assert_eq!(transformed.from_user_id, 5807);
+        assert_eq!(transformed.to_user_id, 42);
+        assert_eq!(transformed.amount, 1575);
+        assert_eq!(transformed.timestamp, 1633036860);
+        assert_eq!(transformed.description, "RECORD ONE");
+        assert_eq!(transformed.status, TransactionStatus::Failure);
+
+        let transaction = Transaction {
+            from_user_id: u64::MAX,
+            to_user_id: 12345,
+            ..Transaction::default()
+        };
+        for spec in ["mask_user_ids:20", "mask_user_ids:25"] {
+            let masked = apply(&[spec], transaction.clone()).unwrap();
+            assert_eq!((masked.from_user_id, masked.to_user_id), (u64::MAX, 12345));
+        }
+        let masked = apply(&["mask_user_ids:19"], transaction).unwrap();
+        assert_eq!(masked.from_user_id, 8446744073709551615);
+    }
+
+    #[test]
+    fn it_fails_on_overflow() {
+        let transaction = Transaction {
+            amount: u64::MAX,
+            ..Transaction::default()
+        };
+        assert!(
+            apply(&["scale_amount:2"], transaction)
+                .is_err_and(|e| matches!(e, ParserError::InvalidValue(_)))
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_transform() {
+        for spec in [
+            "unknown",
+            "scale_amount",
+            "scale_amount:1/0",
+            "timestamp_unit:ms",
+            "timestamp_unit:ms:days",
+            "remap_status:PENDING",
+            "remap_status:PENDING=LOST",
+            "trim_description:1",
+        ] {
+            assert!(spec.parse::<BuiltinTransform>().is_err(), "{}", spec);
+        }
+    }
+}
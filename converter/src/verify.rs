@@ -0,0 +1,84 @@
+//! The `verify` module provides verification of transaction files without converting them.
+//!
+//! Every transaction of a file is read, so that any invalid record fails the verification, and
+//! the trailer and checksums of BIN v2 files are verified along the way.
+
+use crate::Input;
+use parser::{BinHeader, BinParser, Compression, Format};
+
+/// The `Verification` struct is the outcome of verifying a file.
+#[derive(Debug)]
+pub struct Verification {
+    /// The number of transactions in the file.
+    pub records: u64,
+    /// The file header of a BIN v2 file.
+    pub bin_header: Option<BinHeader>,
+}
+
+/// Verifies that every transaction of a file can be read, failing on the first invalid one.
+pub fn verify(input: &Input) -> Result<Verification, Box<dyn std::error::Error>> {
+    let bin_header = match input.format {
+        Format::Bin => BinParser::read_header(&mut Compression::open(&input.path)?)?,
+        _ => None,
+    };
+
+    let mut records = 0;
+    for transaction in input.format.read_iter(Compression::open(&input.path)?) {
+        transaction?;
+        records += 1;
+    }
+
+    Ok(Verification {
+        records,
+        bin_header,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::{BinVersion, Parser, ParserError, Transaction};
+
+    #[test]
+    fn it_detects_flipped_bits() {
+        let transactions = [
+            Transaction {
+                amount: 100,
+                ..Transaction::default()
+            },
+            Transaction {
+                amount: 200,
+                ..Transaction::default()
+            },
+        ];
+        let mut buffer = vec![];
+        BinParser::new(BinVersion::V2, true)
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        let file = tempfile::Builder::new().suffix(".bin").tempfile().unwrap();
+        std::fs::write(file.path(), &buffer).unwrap();
+        let input = Input {
+            path: file.path().to_path_buf(),
+            format: Format::Bin,
+        };
+
+        let verification = verify(&input).unwrap();
+        assert_eq!(verification.records, 2);
+        let header = verification.bin_header.unwrap();
+        assert_eq!(
+            header.flags,
+            BinHeader::RECORD_CRC32C | BinHeader::FILE_SHA256
+        );
+
+        // The last byte of the amount of the second record.
+        let amount = buffer.len() - 44 - 4 - 5 - 8 - 1;
+        assert_eq!(buffer[amount], 200);
+        buffer[amount] ^= 0x01;
+        std::fs::write(file.path(), &buffer).unwrap();
+        let error = verify(&input).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ParserError>(),
+            Some(ParserError::ChecksumMismatch(message)) if message.contains("record 2")
+        ));
+    }
+}
@@ -0,0 +1,13 @@
+//! Generates the Rust types of the Protocol Buffers schema in `proto/` with the `protobuf`
+//! feature. The schema is compiled by `protox`, so `protoc` does not need to be installed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "protobuf")]
+    {
+        println!("cargo:rerun-if-changed=proto");
+        let file_descriptors = protox::compile(["transaction.proto"], ["proto"])?;
+        prost_build::compile_fds(file_descriptors)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+//! The `arrow_parser` module provides functionality to parse financial transactions from Apache
+//! Arrow IPC files and streams.
+//!
+//! Transactions are stored as record batches with the schema described in the `record_batch`
+//! module. The IPC file format (`.arrow`) has a footer and is read from memory, while the IPC
+//! stream format (`.arrows`) is read and written one batch at a time.
+
+use crate::error::ParserError;
+use crate::record_batch::{read_record_batches, to_record_batches, transaction_schema};
+use crate::transaction::Transaction;
+use crate::{Parser, ParserFactory, Transactions};
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_ipc::writer::{FileWriter, StreamWriter};
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io::{BufReader, BufWriter, Cursor, Read, Write};
+use std::str::FromStr;
+
+/// The number of transactions in a record batch.
+const BATCH_SIZE: usize = 8192;
+
+/// The `ArrowIpcFormat` enum represents the layout of Arrow IPC data.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum ArrowIpcFormat {
+    /// The random access file format, with a footer listing the record batches.
+    #[default]
+    File,
+    /// The streaming format, without a footer.
+    Stream,
+}
+
+impl FromStr for ArrowIpcFormat {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "file" => Ok(ArrowIpcFormat::File),
+            "stream" => Ok(ArrowIpcFormat::Stream),
+            _ => Err(ParserError::InvalidValue(format!(
+                "Arrow IPC format: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Display for ArrowIpcFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrowIpcFormat::File => write!(f, "file"),
+            ArrowIpcFormat::Stream => write!(f, "stream"),
+        }
+    }
+}
+
+/// The `ArrowParserFactory` struct is a factory for creating Arrow IPC file parsers.
+pub struct ArrowParserFactory;
+
+impl ParserFactory for ArrowParserFactory {
+    type Parser = ArrowParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        ArrowParser::default()
+    }
+}
+
+/// The `ArrowParser` struct is a parser for Arrow IPC files and streams.
+#[derive(Default)]
+pub struct ArrowParser {
+    format: ArrowIpcFormat,
+}
+
+impl Parser for ArrowParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, mut r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let batches = match self.format {
+            ArrowIpcFormat::File => {
+                let mut buffer = vec![];
+                if let Err(e) = r.read_to_end(&mut buffer) {
+                    return Box::new(std::iter::once(Err(e.into())));
+                }
+                FileReader::try_new(Cursor::new(buffer), None)
+                    .map(|reader| Box::new(reader) as Box<dyn Iterator<Item = _>>)
+            }
+            ArrowIpcFormat::Stream => StreamReader::try_new(BufReader::new(r), None)
+                .map(|reader| Box::new(reader) as Box<dyn Iterator<Item = _>>),
+        };
+
+        match batches {
+            Ok(batches) => read_record_batches(batches, invalid),
+            Err(e) => Box::new(std::iter::once(Err(invalid(e.to_string())))),
+        }
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl ArrowParser {
+    /// Creates a parser for Arrow IPC data in the given format.
+    pub fn new(format: ArrowIpcFormat) -> Self {
+        Self { format }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let batches = to_record_batches(transactions, BATCH_SIZE);
+        let buf_writer = BufWriter::new(writer);
+        let schema = transaction_schema();
+
+        let buf_writer = match self.format {
+            ArrowIpcFormat::File => {
+                let mut ipc_writer =
+                    FileWriter::try_new(buf_writer, &schema).map_err(|e| invalid(e.to_string()))?;
+                for batch in batches {
+                    ipc_writer
+                        .write(&batch?)
+                        .map_err(|e| invalid(e.to_string()))?;
+                }
+                ipc_writer.into_inner()
+            }
+            ArrowIpcFormat::Stream => {
+                let mut ipc_writer = StreamWriter::try_new(buf_writer, &schema)
+                    .map_err(|e| invalid(e.to_string()))?;
+                for batch in batches {
+                    ipc_writer
+                        .write(&batch?)
+                        .map_err(|e| invalid(e.to_string()))?;
+                }
+                ipc_writer.into_inner()
+            }
+        }
+        .map_err(|e| invalid(e.to_string()))?;
+
+        buf_writer
+            .into_inner()
+            .map_err(|e| ParserError::IoError(e.into_error()))?
+            .flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidArrowFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_arrow() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        for format in [ArrowIpcFormat::File, ArrowIpcFormat::Stream] {
+            let parser = ArrowParser::new(format);
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &transactions).unwrap();
+            assert_eq!(
+                buffer.starts_with(b"ARROW1"),
+                format == ArrowIpcFormat::File
+            );
+
+            let read = parser.read_from(&mut buffer.as_slice()).unwrap();
+            assert_eq!(read, transactions);
+
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &[]).unwrap();
+            assert!(parser.read_from(&mut buffer.as_slice()).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_arrow_format() {
+        for format in [ArrowIpcFormat::File, ArrowIpcFormat::Stream] {
+            let parser = ArrowParser::new(format);
+            let mut buffer = vec![];
+            parser
+                .write_to(&mut buffer, &[Transaction::default()])
+                .unwrap();
+            let truncated = &buffer[..buffer.len() / 2];
+
+            for arrow in [b"ARROW1 not arrow".as_slice(), truncated] {
+                assert!(
+                    parser
+                        .read_from(&mut &arrow[..])
+                        .is_err_and(|e| matches!(e, ParserError::InvalidArrowFormat(_))),
+                    "{}",
+                    format
+                );
+            }
+        }
+    }
+}
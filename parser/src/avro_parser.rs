@@ -0,0 +1,370 @@
+//! The `avro_parser` module provides functionality to parse financial transactions from Apache
+//! Avro object container files.
+//!
+//! Transactions are `transaction.Transaction` records of the schema in `AvroParser::SCHEMA`,
+//! which is embedded in every written file. Numbers are `long`s, `timestamp` is a
+//! `timestamp-millis`, and `tx_type` and `status` are enums. Blocks of records are compressed
+//! with the selected `AvroCodec`, and files are read and written one record at a time.
+//!
+//! Files are read with schema resolution, so they may have been written with an older or a newer
+//! schema: fields the schema lacks, such as a `currency`, are skipped, and a missing
+//! `description` is empty.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use apache_avro::types::Value;
+use apache_avro::{Codec, DeflateSettings, Reader, Schema, Writer, ZstandardSettings};
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+static SCHEMA: LazyLock<Schema> =
+    LazyLock::new(|| Schema::parse_str(AvroParser::SCHEMA).expect("valid Avro schema"));
+
+/// The `AvroCodec` enum represents the compression of Avro blocks.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum AvroCodec {
+    /// Blocks are not compressed.
+    #[default]
+    Null,
+    /// Blocks are compressed with deflate.
+    Deflate,
+    /// Blocks are compressed with Snappy.
+    Snappy,
+    /// Blocks are compressed with Zstandard.
+    Zstd,
+}
+
+impl FromStr for AvroCodec {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "null" | "none" => Ok(AvroCodec::Null),
+            "deflate" => Ok(AvroCodec::Deflate),
+            "snappy" => Ok(AvroCodec::Snappy),
+            "zstd" | "zstandard" => Ok(AvroCodec::Zstd),
+            _ => Err(ParserError::InvalidValue(format!("Avro codec: {}", s))),
+        }
+    }
+}
+
+impl Display for AvroCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AvroCodec::Null => write!(f, "null"),
+            AvroCodec::Deflate => write!(f, "deflate"),
+            AvroCodec::Snappy => write!(f, "snappy"),
+            AvroCodec::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl From<AvroCodec> for Codec {
+    fn from(codec: AvroCodec) -> Self {
+        match codec {
+            AvroCodec::Null => Codec::Null,
+            AvroCodec::Deflate => Codec::Deflate(DeflateSettings::default()),
+            AvroCodec::Snappy => Codec::Snappy,
+            AvroCodec::Zstd => Codec::Zstandard(ZstandardSettings::default()),
+        }
+    }
+}
+
+/// The `AvroParserFactory` struct is a factory for creating Avro parsers.
+pub struct AvroParserFactory;
+
+impl ParserFactory for AvroParserFactory {
+    type Parser = AvroParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        AvroParser::default()
+    }
+}
+
+/// The `AvroParser` struct is a parser for Avro object container files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AvroParser {
+    codec: AvroCodec,
+}
+
+impl Parser for AvroParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = match Reader::with_schema(&SCHEMA, BufReader::new(r)) {
+            Ok(reader) => reader,
+            Err(e) => return Box::new(std::iter::once(Err(invalid(e.to_string())))),
+        };
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = reader
+                .next()?
+                .map_err(|e| invalid(e.to_string()))
+                .and_then(Self::from_value);
+            failed = record.is_err();
+            Some(record)
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl AvroParser {
+    /// The Avro schema of transactions.
+    pub const SCHEMA: &'static str = r#"{
+  "type": "record",
+  "name": "Transaction",
+  "namespace": "transaction",
+  "fields": [
+    {"name": "tx_id", "type": "long"},
+    {
+      "name": "tx_type",
+      "type": {
+        "type": "enum",
+        "name": "TransactionType",
+        "symbols": ["DEPOSIT", "WITHDRAWAL", "TRANSFER"]
+      }
+    },
+    {"name": "from_user_id", "type": "long"},
+    {"name": "to_user_id", "type": "long"},
+    {"name": "amount", "type": "long"},
+    {"name": "timestamp", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+    {
+      "name": "status",
+      "type": {
+        "type": "enum",
+        "name": "TransactionStatus",
+        "symbols": ["SUCCESS", "FAILURE", "PENDING"]
+      }
+    },
+    {"name": "description", "type": "string", "default": ""}
+  ]
+}"#;
+
+    /// Creates a parser compressing blocks with the given codec.
+    pub fn new(codec: AvroCodec) -> Self {
+        Self { codec }
+    }
+
+    fn from_value(value: Value) -> Result<Transaction, ParserError> {
+        let Value::Record(fields) = value else {
+            return Err(invalid(format!("Expected a record, found {:?}", value)));
+        };
+
+        let mut record = Record::new(invalid);
+        for (key, value) in fields {
+            let field = record.field(&key)?;
+            let value = match value {
+                Value::Long(number) | Value::TimestampMillis(number) => u64::try_from(number)
+                    .map_err(|_| invalid(format!("Negative {}: {}", key, number)))?
+                    .to_string(),
+                Value::Enum(_, symbol) => symbol,
+                Value::String(text) => text,
+                value => return Err(invalid(format!("Invalid value of {}: {:?}", key, value))),
+            };
+            record.set(field, &value)?;
+        }
+        record.finish()
+    }
+
+    fn to_value(transaction: &Transaction) -> Result<Value, ParserError> {
+        let long = |field: Field| {
+            let number = field.number(transaction).unwrap_or_default();
+            i64::try_from(number)
+                .map_err(|_| invalid(format!("Too large {}: {}", field.key(), number)))
+        };
+
+        let fields = Field::ALL
+            .into_iter()
+            .map(|field| {
+                let value = match field {
+                    Field::TxType => {
+                        Value::Enum(transaction.tx_type as u32, transaction.tx_type.to_string())
+                    }
+                    Field::Timestamp => Value::TimestampMillis(long(field)?),
+                    Field::Status => {
+                        Value::Enum(transaction.status as u32, transaction.status.to_string())
+                    }
+                    Field::Description => Value::String(transaction.description.clone()),
+                    _ => Value::Long(long(field)?),
+                };
+                Ok((field.key().to_string(), value))
+            })
+            .collect::<Result<Vec<_>, ParserError>>()?;
+        Ok(Value::Record(fields))
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut avro_writer =
+            Writer::with_codec(&SCHEMA, BufWriter::new(writer), self.codec.into());
+        for transaction in transactions {
+            avro_writer
+                .append(Self::to_value(transaction?.borrow())?)
+                .map_err(|e| invalid(e.to_string()))?;
+        }
+
+        avro_writer
+            .into_inner()
+            .map_err(|e| invalid(e.to_string()))?
+            .into_inner()
+            .map_err(|e| ParserError::IoError(e.into_error()))?
+            .flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidAvroFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_avro() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        for codec in [
+            AvroCodec::Null,
+            AvroCodec::Deflate,
+            AvroCodec::Snappy,
+            AvroCodec::Zstd,
+        ] {
+            let parser = AvroParser::new(codec);
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &transactions).unwrap();
+            assert!(buffer.starts_with(b"Obj\x01"));
+
+            let read = parser.read_from(&mut buffer.as_slice()).unwrap();
+            assert_eq!(read, transactions, "{}", codec);
+        }
+    }
+
+    #[test]
+    fn it_reads_avro_written_with_other_schema() {
+        let older = Schema::parse_str(
+            r#"{
+  "type": "record",
+  "name": "Transaction",
+  "namespace": "transaction",
+  "fields": [
+    {"name": "tx_id", "type": "int"},
+    {"name": "currency", "type": "string"},
+    {"name": "tx_type", "type": {"type": "enum", "name": "TransactionType", "symbols": ["TRANSFER", "DEPOSIT"]}},
+    {"name": "from_user_id", "type": "long"},
+    {"name": "to_user_id", "type": "long"},
+    {"name": "amount", "type": "long"},
+    {"name": "timestamp", "type": "long"},
+    {"name": "status", "type": {"type": "enum", "name": "TransactionStatus", "symbols": ["PENDING"]}}
+  ]
+}"#,
+        )
+        .unwrap();
+        let mut writer = Writer::new(&older, vec![]);
+        writer
+            .append(Value::Record(vec![
+                ("tx_id".to_string(), Value::Int(7)),
+                ("currency".to_string(), Value::String("EUR".to_string())),
+                (
+                    "tx_type".to_string(),
+                    Value::Enum(0, "TRANSFER".to_string()),
+                ),
+                ("from_user_id".to_string(), Value::Long(1)),
+                ("to_user_id".to_string(), Value::Long(2)),
+                ("amount".to_string(), Value::Long(300)),
+                ("timestamp".to_string(), Value::Long(1633036860000)),
+                ("status".to_string(), Value::Enum(0, "PENDING".to_string())),
+            ]))
+            .unwrap();
+        let buffer = writer.into_inner().unwrap();
+
+        let read = AvroParserFactory
+            .create_parser()
+            .read_from(&mut buffer.as_slice())
+            .unwrap();
+        assert_eq!(
+            read,
+            [Transaction::new(
+                7,
+                crate::TransactionType::Transfer,
+                1,
+                2,
+                300,
+                1633036860000,
+                crate::TransactionStatus::Pending,
+                "".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_avro_format() {
+        let parser = AvroParser::default();
+        let mut buffer = vec![];
+        parser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+        let truncated = &buffer[..buffer.len() - 1];
+
+        for avro in [b"Obj\x01 not avro".as_slice(), truncated] {
+            assert!(
+                parser
+                    .read_from(&mut &avro[..])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidAvroFormat(_)))
+            );
+        }
+
+        let too_large = Transaction {
+            tx_id: u64::MAX,
+            ..Transaction::default()
+        };
+        assert!(
+            parser
+                .write_to(&mut vec![], &[too_large])
+                .is_err_and(|e| matches!(e, ParserError::InvalidAvroFormat(_)))
+        );
+    }
+}
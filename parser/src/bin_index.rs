@@ -0,0 +1,825 @@
+//! The `bin_index` module provides random access to the records of BIN files.
+//!
+//! A `BinIndex` maps the position of every record to its byte offset and every `tx_id` to the
+//! positions of its records, and keeps the timestamp range of every block of
+//! `BinIndex::DEFAULT_BLOCK_SIZE` records. It is written by `BinParser::write_indexed` to a
+//! sidecar next to the BIN file, e.g. `records.bin.idx`, or built from an existing file, and is
+//! itself a file of big-endian numbers: the magic sequence `YPBI`, the version (`u16`, 2), the
+//! block size (`u32`), the identity of the indexed BIN file, which is its size (`u64`) and the
+//! creation time of its header (`u64`, 0 for a v1 file), and the record count (`u64`), followed
+//! by the offsets of the records (`u64`), the pairs of `tx_id` and position (`u64`s) sorted by
+//! `tx_id`, and the smallest and largest timestamp of every block (`u64`s).
+//!
+//! As every table has entries of a fixed size, an index is read from disk one entry at a time:
+//! offsets by position and `tx_id`s by binary search. While it is written, the offsets and runs
+//! of sorted `tx_id`s are spilled to temporary files, and the runs are merged at the end.
+//!
+//! A `BinReader` seeks to records of an uncompressed BIN file of either version by position,
+//! `tx_id` or time range. With an index it jumps to the records, and without one it walks the
+//! file from its start, skipping every record by its size after reading only its `tx_id` and
+//! timestamp. An index of another file is rejected by its identity and, for a v2 file, by the
+//! record count of the trailer; every record an index points to is checked to be the one
+//! sought. Records are read one at a time, verifying their CRC32C when the file has them; the
+//! SHA-256 of a whole file is only verified by reading it with `BinParser`.
+
+use crate::bin_parser::{MAGIC, TRAILER_MAGIC, read_magic, read_record};
+use crate::error::ParserError;
+use crate::error::ParserError::{InvalidBinFormat, InvalidBinIndex};
+use crate::transaction::Transaction;
+use crate::{BinHeader, BinParser};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::ErrorKind::UnexpectedEof;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// The magic sequence of a BIN index.
+const INDEX_MAGIC: &str = "YPBI";
+
+/// The version of BIN indexes.
+const INDEX_VERSION: u16 = 2;
+
+/// The size of the header of a BIN index.
+const INDEX_HEADER_SIZE: u64 = 4 + 2 + 4 + 8 + 8 + 8;
+
+/// The number of pairs of `tx_id` and position sorted in memory before spilling to disk.
+pub(crate) const ID_BUFFER_SIZE: usize = 1 << 20;
+
+/// The size of the fields of a record before its timestamp: `tx_id`, `tx_type`, the user IDs
+/// and `amount`.
+const TIMESTAMP_OFFSET: u32 = 8 + 1 + 8 + 8 + 8;
+
+/// The `BinIndex` struct is an index of the records of a BIN file, read from `R`.
+#[derive(Debug)]
+pub struct BinIndex<R = File> {
+    r: R,
+    block_size: u32,
+    file_size: u64,
+    created: u64,
+    count: u64,
+}
+
+impl BinIndex {
+    /// The number of records of a block with a timestamp range.
+    pub const DEFAULT_BLOCK_SIZE: u32 = 1024;
+
+    /// Returns the path of the index sidecar of a BIN file, e.g. `records.bin.idx`.
+    pub fn sidecar_path(path: &Path) -> PathBuf {
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".idx");
+        PathBuf::from(sidecar)
+    }
+
+    /// Opens the index sidecar of a BIN file.
+    pub fn open_sidecar(path: &Path) -> Result<Self, ParserError> {
+        Self::open(File::open(Self::sidecar_path(path))?)
+    }
+
+    /// Builds the index of a BIN file, reading only the `tx_id` and timestamp of every record,
+    /// and writes it to `writer`.
+    pub fn build<R: Read + Seek, W: Write>(r: R, writer: &mut W) -> Result<(), ParserError> {
+        let mut reader = BinReader::new(r)?;
+        let mut index = IndexWriter::new(Self::DEFAULT_BLOCK_SIZE, ID_BUFFER_SIZE)?;
+        if let Some(header) = reader.header() {
+            index.set_created(header.created);
+        }
+        while let Some(key) = reader.next_key()? {
+            index.push(key.offset, key.tx_id, key.timestamp)?;
+        }
+        let file_size = reader.r.seek(SeekFrom::End(0))?;
+        index.finish(file_size, writer)
+    }
+}
+
+impl<R: Read + Seek> BinIndex<R> {
+    /// Opens an index, reading only its header.
+    pub fn open(mut r: R) -> Result<Self, ParserError> {
+        r.rewind()?;
+        let mut magic = [0x00; 4];
+        r.read_exact(&mut magic)?;
+        if magic != INDEX_MAGIC.as_bytes() {
+            return Err(InvalidBinIndex("Wrong MAGIC sequence".to_string()));
+        }
+        let version = r.read_u16::<BigEndian>()?;
+        if version != INDEX_VERSION {
+            return Err(InvalidBinIndex(format!("Unsupported version: {}", version)));
+        }
+        let block_size = r.read_u32::<BigEndian>()?;
+        if block_size == 0 {
+            return Err(InvalidBinIndex("Empty blocks".to_string()));
+        }
+        let file_size = r.read_u64::<BigEndian>()?;
+        let created = r.read_u64::<BigEndian>()?;
+        let count = r.read_u64::<BigEndian>()?;
+
+        let size = r.seek(SeekFrom::End(0))?;
+        let expected = count
+            .checked_mul(8 + 16)
+            .and_then(|size| size.checked_add(count.div_ceil(block_size as u64) * 16))
+            .and_then(|size| size.checked_add(INDEX_HEADER_SIZE));
+        if expected != Some(size) {
+            return Err(InvalidBinIndex(format!(
+                "Index of {} records has {} bytes",
+                count, size
+            )));
+        }
+
+        Ok(Self {
+            r,
+            block_size,
+            file_size,
+            created,
+            count,
+        })
+    }
+
+    /// Returns the number of indexed records.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns whether the indexed file has no records.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the size of the indexed file in bytes.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Returns the creation time of the header of the indexed file, which is 0 for a v1 file.
+    pub fn created(&self) -> u64 {
+        self.created
+    }
+
+    /// Returns the byte offset of the record at a position.
+    pub fn offset(&mut self, position: u64) -> Result<Option<u64>, ParserError> {
+        if position >= self.count {
+            return Ok(None);
+        }
+        self.r
+            .seek(SeekFrom::Start(INDEX_HEADER_SIZE + position * 8))?;
+        Ok(Some(self.r.read_u64::<BigEndian>()?))
+    }
+
+    /// Returns the position of the first record with a `tx_id`.
+    pub fn position(&mut self, tx_id: u64) -> Result<Option<u64>, ParserError> {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.id(middle)?.0 < tx_id {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        if low == self.count {
+            return Ok(None);
+        }
+
+        let (id, position) = self.id(low)?;
+        if id != tx_id {
+            return Ok(None);
+        }
+        if position >= self.count {
+            return Err(InvalidBinIndex(format!(
+                "Wrong position of tx_id {}: {}",
+                tx_id, position
+            )));
+        }
+        Ok(Some(position))
+    }
+
+    /// Returns the positions of the records of every block with timestamps between `from` and
+    /// `to`, inclusive.
+    pub fn blocks(&mut self, from: u64, to: u64) -> Result<Vec<Range<u64>>, ParserError> {
+        let block_size = self.block_size as u64;
+        let block_count = self.count.div_ceil(block_size);
+        self.r
+            .seek(SeekFrom::Start(INDEX_HEADER_SIZE + self.count * (8 + 16)))?;
+        let mut r = BufReader::new((&mut self.r).take(block_count * 16));
+
+        let mut blocks = vec![];
+        for block in 0..block_count {
+            let min = r.read_u64::<BigEndian>()?;
+            let max = r.read_u64::<BigEndian>()?;
+            if min <= to && max >= from {
+                let start = block * block_size;
+                blocks.push(start..(start + block_size).min(self.count));
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Reads the pair of `tx_id` and position at an entry of the sorted table.
+    fn id(&mut self, entry: u64) -> Result<(u64, u64), ParserError> {
+        self.r.seek(SeekFrom::Start(
+            INDEX_HEADER_SIZE + self.count * 8 + entry * 16,
+        ))?;
+        let tx_id = self.r.read_u64::<BigEndian>()?;
+        let position = self.r.read_u64::<BigEndian>()?;
+        Ok((tx_id, position))
+    }
+}
+
+/// The `IndexWriter` struct writes the index of a BIN file whose records are pushed as the
+/// file is written.
+///
+/// The offsets are spilled to a temporary file as they are pushed, and the pairs of `tx_id`
+/// and position are sorted in runs of `buffer_size` pairs, each spilled to a temporary file
+/// and merged when the index is written.
+pub(crate) struct IndexWriter {
+    block_size: u32,
+    buffer_size: usize,
+    created: u64,
+    count: u64,
+    offsets: BufWriter<File>,
+    ids: Vec<(u64, u64)>,
+    runs: Vec<File>,
+    /// The smallest and largest timestamp of every block.
+    blocks: Vec<(u64, u64)>,
+}
+
+impl IndexWriter {
+    /// Creates an index with the given block size, sorting `buffer_size` pairs of `tx_id` and
+    /// position in memory.
+    pub(crate) fn new(block_size: u32, buffer_size: usize) -> Result<Self, ParserError> {
+        Ok(Self {
+            block_size,
+            buffer_size: buffer_size.max(1),
+            created: 0,
+            count: 0,
+            offsets: BufWriter::new(tempfile::tempfile()?),
+            ids: vec![],
+            runs: vec![],
+            blocks: vec![],
+        })
+    }
+
+    /// Sets the creation time of the header of the indexed v2 file.
+    pub(crate) fn set_created(&mut self, created: u64) {
+        self.created = created;
+    }
+
+    /// Adds the next record of the file.
+    pub(crate) fn push(
+        &mut self,
+        offset: u64,
+        tx_id: u64,
+        timestamp: u64,
+    ) -> Result<(), ParserError> {
+        if self.count.is_multiple_of(self.block_size as u64) {
+            self.blocks.push((timestamp, timestamp));
+        } else if let Some((min, max)) = self.blocks.last_mut() {
+            *min = timestamp.min(*min);
+            *max = timestamp.max(*max);
+        }
+        self.offsets.write_u64::<BigEndian>(offset)?;
+        self.ids.push((tx_id, self.count));
+        self.count += 1;
+        if self.ids.len() >= self.buffer_size {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Sorts the buffered pairs and writes them to a temporary file.
+    fn spill(&mut self) -> Result<(), ParserError> {
+        self.ids.sort_unstable();
+        let mut run = BufWriter::new(tempfile::tempfile()?);
+        for (tx_id, position) in self.ids.drain(..) {
+            run.write_u64::<BigEndian>(tx_id)?;
+            run.write_u64::<BigEndian>(position)?;
+        }
+        let mut run = run.into_inner().map_err(|e| e.into_error())?;
+        run.rewind()?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// Writes the index of a file of `file_size` bytes after all records were pushed.
+    pub(crate) fn finish<W: Write>(
+        mut self,
+        file_size: u64,
+        writer: &mut W,
+    ) -> Result<(), ParserError> {
+        if !self.runs.is_empty() && !self.ids.is_empty() {
+            self.spill()?;
+        }
+
+        writer.write_all(INDEX_MAGIC.as_bytes())?;
+        writer.write_u16::<BigEndian>(INDEX_VERSION)?;
+        writer.write_u32::<BigEndian>(self.block_size)?;
+        writer.write_u64::<BigEndian>(file_size)?;
+        writer.write_u64::<BigEndian>(self.created)?;
+        writer.write_u64::<BigEndian>(self.count)?;
+
+        let mut offsets = self.offsets.into_inner().map_err(|e| e.into_error())?;
+        offsets.rewind()?;
+        std::io::copy(&mut BufReader::new(offsets), writer)?;
+
+        if self.runs.is_empty() {
+            self.ids.sort_unstable();
+            for (tx_id, position) in self.ids {
+                writer.write_u64::<BigEndian>(tx_id)?;
+                writer.write_u64::<BigEndian>(position)?;
+            }
+        } else {
+            let mut runs = self
+                .runs
+                .into_iter()
+                .map(BufReader::new)
+                .collect::<Vec<_>>();
+            let mut heads = BinaryHeap::new();
+            for (run, r) in runs.iter_mut().enumerate() {
+                if let Some(id) = read_id(r)? {
+                    heads.push(Reverse((id, run)));
+                }
+            }
+            while let Some(Reverse(((tx_id, position), run))) = heads.pop() {
+                writer.write_u64::<BigEndian>(tx_id)?;
+                writer.write_u64::<BigEndian>(position)?;
+                if let Some(id) = read_id(&mut runs[run])? {
+                    heads.push(Reverse((id, run)));
+                }
+            }
+        }
+
+        for (min, max) in self.blocks {
+            writer.write_u64::<BigEndian>(min)?;
+            writer.write_u64::<BigEndian>(max)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the next pair of `tx_id` and position of a spilled run, which is `None` at its end.
+fn read_id<R: Read>(r: &mut R) -> Result<Option<(u64, u64)>, ParserError> {
+    let tx_id = match r.read_u64::<BigEndian>() {
+        Ok(tx_id) => tx_id,
+        Err(e) if e.kind() == UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some((tx_id, r.read_u64::<BigEndian>()?)))
+}
+
+/// The `RecordKey` struct is the location, `tx_id` and timestamp of a record.
+pub(crate) struct RecordKey {
+    offset: u64,
+    tx_id: u64,
+    timestamp: u64,
+}
+
+/// The `BinReader` struct reads the records of a BIN file at random, with an index read from
+/// `X`.
+pub struct BinReader<R, X = File> {
+    r: R,
+    header: Option<BinHeader>,
+    index: Option<BinIndex<X>>,
+    /// The offset of the first record.
+    start: u64,
+    /// The position of the next record.
+    position: u64,
+}
+
+impl<R: Read + Seek> BinReader<R> {
+    /// Creates a reader at the first record of a file, walking the file to seek.
+    pub fn new(r: R) -> Result<Self, ParserError> {
+        Self::open(r)
+    }
+}
+
+impl<R: Read + Seek, X: Read + Seek> BinReader<R, X> {
+    /// Creates a reader at the first record of a file, using its index to seek.
+    ///
+    /// The index must have the identity of the file, and the record count of the trailer of a
+    /// v2 file.
+    pub fn with_index(mut r: R, index: BinIndex<X>) -> Result<Self, ParserError> {
+        let file_size = r.seek(SeekFrom::End(0))?;
+        if file_size != index.file_size {
+            return Err(InvalidBinIndex(format!(
+                "Index of a file of {} bytes, but the file has {}",
+                index.file_size, file_size
+            )));
+        }
+
+        let mut reader = Self::open(r)?;
+        let created = reader.header.as_ref().map_or(0, |header| header.created);
+        if created != index.created {
+            return Err(InvalidBinIndex(format!(
+                "Index of a file created at {}, but the file was created at {}",
+                index.created, created
+            )));
+        }
+        if reader.header.is_some() {
+            let count = reader.trailer_count()?;
+            if count != index.len() {
+                return Err(InvalidBinIndex(format!(
+                    "Index of {} records, but the file has {}",
+                    index.len(),
+                    count
+                )));
+            }
+        }
+        reader.index = Some(index);
+        Ok(reader)
+    }
+
+    fn open(mut r: R) -> Result<Self, ParserError> {
+        r.rewind()?;
+        let header = BinParser::read_header(&mut r)?;
+        let start = match header {
+            Some(_) => r.stream_position()?,
+            None => 0,
+        };
+        r.seek(SeekFrom::Start(start))?;
+
+        Ok(Self {
+            r,
+            header,
+            index: None,
+            start,
+            position: 0,
+        })
+    }
+
+    /// Returns the header of a BIN v2 file.
+    pub fn header(&self) -> Option<&BinHeader> {
+        self.header.as_ref()
+    }
+
+    /// Returns the index used to seek.
+    pub fn index(&self) -> Option<&BinIndex<X>> {
+        self.index.as_ref()
+    }
+
+    /// Returns the position of the next record.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Seeks to the first record.
+    pub fn rewind(&mut self) -> Result<(), ParserError> {
+        self.r.seek(SeekFrom::Start(self.start))?;
+        self.position = 0;
+        Ok(())
+    }
+
+    /// Seeks to the record at a position, returning whether the file has it.
+    pub fn seek_to_position(&mut self, position: u64) -> Result<bool, ParserError> {
+        if let Some(index) = &mut self.index {
+            let Some(offset) = index.offset(position)? else {
+                return Ok(false);
+            };
+            self.r.seek(SeekFrom::Start(offset))?;
+            if read_magic(&mut self.r)?.is_none_or(|magic| magic != MAGIC.as_bytes()) {
+                return Err(InvalidBinIndex(format!(
+                    "No record {} at offset {}",
+                    position, offset
+                )));
+            }
+            self.r.seek(SeekFrom::Start(offset))?;
+            self.position = position;
+            return Ok(true);
+        }
+
+        self.rewind()?;
+        while self.position < position {
+            if self.next_key()?.is_none() {
+                return Ok(false);
+            }
+        }
+        let offset = self.r.stream_position()?;
+        let found = read_magic(&mut self.r)?.is_some_and(|magic| magic == MAGIC.as_bytes());
+        self.r.seek(SeekFrom::Start(offset))?;
+        Ok(found)
+    }
+
+    /// Seeks to the first record with a `tx_id`, returning whether the file has one.
+    pub fn seek_to_id(&mut self, tx_id: u64) -> Result<bool, ParserError> {
+        if let Some(index) = &mut self.index {
+            let Some(position) = index.position(tx_id)? else {
+                return Ok(false);
+            };
+            self.seek_to_position(position)?;
+            let offset = self.r.stream_position()?;
+            let key = self.next_key()?;
+            self.r.seek(SeekFrom::Start(offset))?;
+            self.position = position;
+            return match key {
+                Some(key) if key.tx_id == tx_id => Ok(true),
+                _ => Err(InvalidBinIndex(format!(
+                    "Record {} does not have tx_id {}",
+                    position, tx_id
+                ))),
+            };
+        }
+
+        self.rewind()?;
+        while let Some(key) = self.next_key()? {
+            if key.tx_id == tx_id {
+                self.r.seek(SeekFrom::Start(key.offset))?;
+                self.position -= 1;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Reads the next record, which is `None` at the end of the file.
+    pub fn read_next(&mut self) -> Result<Option<Transaction>, ParserError> {
+        let offset = self.r.stream_position()?;
+        match read_magic(&mut self.r)? {
+            Some(magic) if magic == MAGIC.as_bytes() => {
+                let crc = self.has_crc();
+                let transaction = read_record(&mut self.r, magic, crc, self.position + 1)?;
+                self.position += 1;
+                Ok(Some(transaction))
+            }
+            Some(magic) if self.header.is_some() && magic == TRAILER_MAGIC.as_bytes() => {
+                self.r.seek(SeekFrom::Start(offset))?;
+                Ok(None)
+            }
+            Some(_) => Err(InvalidBinFormat(format!(
+                "Wrong MAGIC sequence at offset {}",
+                offset
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the records with timestamps between `from` and `to`, inclusive, in file order.
+    pub fn read_time_range(&mut self, from: u64, to: u64) -> Result<Vec<Transaction>, ParserError> {
+        let mut transactions = vec![];
+        let in_range = |timestamp: u64| (from..=to).contains(&timestamp);
+
+        if let Some(index) = &mut self.index {
+            for block in index.blocks(from, to)? {
+                self.seek_to_position(block.start)?;
+                for _ in block {
+                    match self.read_next()? {
+                        Some(transaction) if in_range(transaction.timestamp) => {
+                            transactions.push(transaction)
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+            return Ok(transactions);
+        }
+
+        self.rewind()?;
+        while let Some(key) = self.next_key()? {
+            if in_range(key.timestamp) {
+                self.r.seek(SeekFrom::Start(key.offset))?;
+                self.position -= 1;
+                transactions.extend(self.read_next()?);
+            }
+        }
+        Ok(transactions)
+    }
+
+    /// Reads the key of the next record and skips the rest of it.
+    pub(crate) fn next_key(&mut self) -> Result<Option<RecordKey>, ParserError> {
+        let offset = self.r.stream_position()?;
+        match read_magic(&mut self.r)? {
+            Some(magic) if magic == MAGIC.as_bytes() => {}
+            Some(magic) if self.header.is_some() && magic == TRAILER_MAGIC.as_bytes() => {
+                self.r.seek(SeekFrom::Start(offset))?;
+                return Ok(None);
+            }
+            Some(_) => {
+                return Err(InvalidBinFormat(format!(
+                    "Wrong MAGIC sequence at offset {}",
+                    offset
+                )));
+            }
+            None => return Ok(None),
+        }
+
+        let record_size = self.r.read_u32::<BigEndian>()?;
+        if record_size < TIMESTAMP_OFFSET + 8 {
+            return Err(InvalidBinFormat(format!(
+                "Wrong size of record {}",
+                self.position + 1
+            )));
+        }
+        let tx_id = self.r.read_u64::<BigEndian>()?;
+        self.r.seek_relative((TIMESTAMP_OFFSET - 8) as i64)?;
+        let timestamp = self.r.read_u64::<BigEndian>()?;
+        let crc_size = if self.has_crc() { 4 } else { 0 };
+        self.r
+            .seek_relative((record_size - TIMESTAMP_OFFSET - 8) as i64 + crc_size)?;
+        self.position += 1;
+
+        Ok(Some(RecordKey {
+            offset,
+            tx_id,
+            timestamp,
+        }))
+    }
+
+    /// Reads the record count of the trailer of a v2 file.
+    fn trailer_count(&mut self) -> Result<u64, ParserError> {
+        let sha_size = match &self.header {
+            Some(header) if header.flags & BinHeader::FILE_SHA256 != 0 => 32,
+            _ => 0,
+        };
+        self.r.seek(SeekFrom::End(-12 - sha_size))?;
+        if read_magic(&mut self.r)?.is_none_or(|magic| magic != TRAILER_MAGIC.as_bytes()) {
+            return Err(InvalidBinFormat("Missing trailer".to_string()));
+        }
+        let count = self.r.read_u64::<BigEndian>()?;
+        self.r.seek(SeekFrom::Start(self.start))?;
+        Ok(count)
+    }
+
+    fn has_crc(&self) -> bool {
+        self.header
+            .as_ref()
+            .is_some_and(|header| header.flags & BinHeader::RECORD_CRC32C != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinVersion, Parser};
+    use std::io::Cursor;
+
+    fn check_seeks<R: Read + Seek, X: Read + Seek>(
+        mut reader: BinReader<R, X>,
+        transactions: &[Transaction],
+    ) {
+        let (from, to) = (transactions[600].timestamp, transactions[620].timestamp);
+        let in_range = transactions
+            .iter()
+            .filter(|transaction| (from..=to).contains(&transaction.timestamp))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(!in_range.is_empty());
+
+        assert!(reader.seek_to_id(transactions[700].tx_id).unwrap());
+        assert_eq!(reader.position(), 700);
+        assert_eq!(
+            reader.read_next().unwrap().as_ref(),
+            Some(&transactions[700])
+        );
+        assert_eq!(
+            reader.read_next().unwrap().as_ref(),
+            Some(&transactions[701])
+        );
+
+        assert!(reader.seek_to_position(999).unwrap());
+        assert_eq!(reader.read_next().unwrap().as_ref(), transactions.last());
+        assert_eq!(reader.read_next().unwrap(), None);
+        assert!(!reader.seek_to_position(1000).unwrap());
+        assert!(!reader.seek_to_id(u64::MAX).unwrap());
+
+        assert_eq!(reader.read_time_range(from, to).unwrap(), in_range);
+    }
+
+    #[test]
+    fn it_seeks_to_bin_records() {
+        let transactions = BinParser::default()
+            .read_from(&mut File::open("../samples/records_example.bin").unwrap())
+            .unwrap();
+        let mut v2 = Cursor::new(vec![]);
+        let mut v2_index = vec![];
+        BinParser::new(BinVersion::V2, true)
+            .write_indexed(&mut v2, &mut v2_index, transactions.iter().cloned().map(Ok))
+            .unwrap();
+        let v2 = v2.into_inner();
+        let mut built = vec![];
+        BinIndex::build(Cursor::new(&v2), &mut built).unwrap();
+        assert!(built == v2_index);
+
+        let v1 = std::fs::read("../samples/records_example.bin").unwrap();
+        let mut v1_index = vec![];
+        BinIndex::build(Cursor::new(&v1), &mut v1_index).unwrap();
+        let v1_index = BinIndex::open(Cursor::new(v1_index)).unwrap();
+        assert_eq!(v1_index.len(), 1000);
+        assert_eq!(v1_index.file_size(), v1.len() as u64);
+        assert_eq!(v1_index.created(), 0);
+        let v2_index = BinIndex::open(Cursor::new(v2_index)).unwrap();
+
+        check_seeks(BinReader::new(Cursor::new(&v1)).unwrap(), &transactions);
+        check_seeks(
+            BinReader::with_index(Cursor::new(&v1), v1_index).unwrap(),
+            &transactions,
+        );
+        check_seeks(BinReader::new(Cursor::new(&v2)).unwrap(), &transactions);
+        check_seeks(
+            BinReader::with_index(Cursor::new(&v2), v2_index).unwrap(),
+            &transactions,
+        );
+    }
+
+    #[test]
+    fn it_merges_spilled_bin_index_ids() {
+        let keys = (0..1000u64)
+            .map(|position| (position * 100, position * 7919 % 500, position))
+            .collect::<Vec<_>>();
+        let write = |buffer_size| {
+            let mut index = IndexWriter::new(16, buffer_size).unwrap();
+            for (offset, tx_id, timestamp) in &keys {
+                index.push(*offset, *tx_id, *timestamp).unwrap();
+            }
+            let mut bytes = vec![];
+            index.finish(100_000, &mut bytes).unwrap();
+            bytes
+        };
+
+        let spilled = write(64);
+        assert!(spilled == write(usize::MAX));
+
+        let mut index = BinIndex::open(Cursor::new(spilled)).unwrap();
+        assert_eq!(index.len(), 1000);
+        assert_eq!(index.offset(999).unwrap(), Some(99_900));
+        assert_eq!(index.offset(1000).unwrap(), None);
+        assert_eq!(index.position(7919 % 500).unwrap(), Some(1));
+        assert_eq!(index.position(0).unwrap(), Some(0));
+        assert_eq!(index.position(500).unwrap(), None);
+        assert_eq!(index.blocks(40, 50).unwrap(), [32..48, 48..64]);
+    }
+
+    #[test]
+    fn it_is_invalid_bin_index() {
+        let mut bin = Cursor::new(vec![]);
+        let mut index = vec![];
+        BinParser::default()
+            .write_indexed(&mut bin, &mut index, [Ok(Transaction::default())])
+            .unwrap();
+        let mut bin = bin.into_inner();
+
+        for index in [&index[..index.len() - 1], b"YPBN".as_slice()] {
+            assert!(
+                BinIndex::open(Cursor::new(index))
+                    .is_err_and(|e| matches!(e, InvalidBinIndex(_) | ParserError::IoError(_)))
+            );
+        }
+
+        let mut wrong_position = index.clone();
+        let last = wrong_position.len() - 17;
+        wrong_position[last] = 1;
+        let mut wrong_position = BinIndex::open(Cursor::new(wrong_position)).unwrap();
+        assert!(
+            wrong_position
+                .position(0)
+                .is_err_and(|e| matches!(e, InvalidBinIndex(_)))
+        );
+
+        // A file of the same size with other records.
+        let mut other = bin.clone();
+        other[8] = 1;
+        let open = || BinIndex::open(Cursor::new(index.clone())).unwrap();
+        let mut reader = BinReader::with_index(Cursor::new(&other), open()).unwrap();
+        assert!(
+            reader
+                .seek_to_id(0)
+                .is_err_and(|e| matches!(e, InvalidBinIndex(_)))
+        );
+
+        bin.extend_from_slice(&bin.clone());
+        assert!(
+            BinReader::with_index(Cursor::new(&bin), open())
+                .is_err_and(|e| matches!(e, InvalidBinIndex(_)))
+        );
+    }
+
+    #[test]
+    fn it_is_stale_bin_v2_index() {
+        let transactions = [Transaction::default(), Transaction::default()];
+        let parser = BinParser::new(BinVersion::V2, false);
+        let mut bin = Cursor::new(vec![]);
+        let mut index = vec![];
+        parser
+            .write_indexed(&mut bin, &mut index, transactions.iter().cloned().map(Ok))
+            .unwrap();
+        let bin = bin.into_inner();
+        let open = || BinIndex::open(Cursor::new(index.clone())).unwrap();
+        assert!(BinReader::with_index(Cursor::new(&bin), open()).is_ok());
+
+        // The same records written at another time.
+        let mut rewritten = bin.clone();
+        rewritten[25] ^= 1;
+        assert!(
+            BinReader::with_index(Cursor::new(&rewritten), open())
+                .is_err_and(|e| matches!(e, InvalidBinIndex(_)))
+        );
+
+        let mut wrong_count = bin.clone();
+        let last = wrong_count.len() - 1;
+        wrong_count[last] = 3;
+        assert!(
+            BinReader::with_index(Cursor::new(&wrong_count), open())
+                .is_err_and(|e| matches!(e, InvalidBinIndex(_)))
+        );
+    }
+}
@@ -1,15 +1,185 @@
 //! The `bin_parser` module provides functionality to parse financial transactions from BIN files.
+//!
+//! A BIN v1 file is a sequence of records, each the magic sequence `YPBN`, the size of the rest
+//! of the record as a `u32` and the fields of a transaction. A BIN v2 file starts with a header:
+//! the magic sequence `YPBH`, the version (`u16`, 2), flags (`u16`), the field set (`u16`, a bit
+//! per field in the order of `Field::ALL`), the record count (`u64`, `u64::MAX` when the records
+//! were streamed to a writer that cannot seek back to it), the creation time in milliseconds since the Unix epoch (`u64`) and the
+//! producer (a `u16` length and UTF-8 bytes). Its records are those of v1, and it ends with a
+//! trailer: the magic sequence `YPBT` and the record count (`u64`). Numbers are big-endian.
+//!
+//! With the `BinHeader::RECORD_CRC32C` flag, every record of a v2 file is followed by the CRC32C
+//! of its bytes (`u32`), and with the `BinHeader::FILE_SHA256` flag, the trailer ends with the
+//! SHA-256 of all bytes of the file before it.
+//!
+//! `BinParser` detects the version of a file from its first magic sequence, checks that a v2
+//! file ends with a trailer matching its records, and verifies the checksums of the file.
 
+use crate::bin_index::{BinIndex, ID_BUFFER_SIZE, IndexWriter};
 use crate::error::ParserError;
 use crate::error::ParserError::InvalidBinFormat;
-use crate::transaction::{Transaction, TransactionStatus, TransactionType};
-use crate::{Parser, ParserFactory};
+use crate::transaction::{Field, Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use sha2::{Digest, Sha256};
+use std::borrow::Borrow;
+use std::fmt::Display;
 use std::io::ErrorKind::UnexpectedEof;
-use std::io::{Read, Write};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// The magic sequence for the BIN file.
-const MAGIC: &str = "YPBN";
+pub(crate) const MAGIC: &str = "YPBN";
+
+/// The magic sequence of the header of a BIN v2 file.
+const HEADER_MAGIC: &str = "YPBH";
+
+/// The magic sequence of the trailer of a BIN v2 file.
+pub(crate) const TRAILER_MAGIC: &str = "YPBT";
+
+/// The record count of a header written before the number of records was known.
+const UNKNOWN_COUNT: u64 = u64::MAX;
+
+/// The offset of the record count in the header of a BIN v2 file.
+const RECORD_COUNT_OFFSET: u64 = 10;
+
+/// The `BinVersion` enum represents the version of written BIN files.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum BinVersion {
+    /// Records without a file header or trailer.
+    #[default]
+    V1,
+    /// Records between a file header and a trailer.
+    V2,
+}
+
+impl FromStr for BinVersion {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "1" | "v1" => Ok(BinVersion::V1),
+            "2" | "v2" => Ok(BinVersion::V2),
+            _ => Err(ParserError::InvalidValue(format!("BIN version: {}", s))),
+        }
+    }
+}
+
+impl Display for BinVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinVersion::V1 => write!(f, "1"),
+            BinVersion::V2 => write!(f, "2"),
+        }
+    }
+}
+
+/// The `BinHeader` struct represents the file header of a BIN v2 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinHeader {
+    /// The version of the file.
+    pub version: u16,
+    /// Flags of optional features of the file.
+    pub flags: u16,
+    /// The fields of every record, in order.
+    pub fields: Vec<Field>,
+    /// The number of records, unless they were streamed.
+    pub record_count: Option<u64>,
+    /// The creation time in milliseconds since the Unix epoch.
+    pub created: u64,
+    /// The name and version of the program that wrote the file.
+    pub producer: String,
+}
+
+impl BinHeader {
+    /// The version of files with a header.
+    pub const VERSION: u16 = 2;
+
+    /// The flag of files with a CRC32C after every record.
+    pub const RECORD_CRC32C: u16 = 0x0001;
+
+    /// The flag of files with a SHA-256 of the file in the trailer.
+    pub const FILE_SHA256: u16 = 0x0002;
+
+    fn new(record_count: Option<u64>, flags: u16) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        Self {
+            version: Self::VERSION,
+            flags,
+            fields: Field::ALL.to_vec(),
+            record_count,
+            created,
+            producer: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// Reads the header following its magic sequence.
+    fn read_from<R: Read>(r: &mut R) -> Result<Self, ParserError> {
+        let version = r.read_u16::<BigEndian>()?;
+        if version != Self::VERSION {
+            return Err(InvalidBinFormat(format!(
+                "Unsupported version: {}",
+                version
+            )));
+        }
+        let flags = r.read_u16::<BigEndian>()?;
+        if flags & !(Self::RECORD_CRC32C | Self::FILE_SHA256) != 0 {
+            return Err(InvalidBinFormat(format!(
+                "Unsupported flags: {:#06x}",
+                flags
+            )));
+        }
+        let field_set = r.read_u16::<BigEndian>()?;
+        let fields = Field::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| field_set & (1 << index) != 0)
+            .map(|(_, field)| field)
+            .collect::<Vec<_>>();
+        if fields != Field::ALL || field_set >> Field::ALL.len() != 0 {
+            return Err(InvalidBinFormat(format!(
+                "Unsupported field set: {:#06x}",
+                field_set
+            )));
+        }
+        let record_count = Some(r.read_u64::<BigEndian>()?).filter(|&n| n != UNKNOWN_COUNT);
+        let created = r.read_u64::<BigEndian>()?;
+        let producer_length = r.read_u16::<BigEndian>()?;
+        let mut producer = vec![0x00; producer_length as usize];
+        r.read_exact(&mut producer)?;
+        let producer = String::from_utf8(producer)
+            .map_err(|_| InvalidBinFormat("Wrong producer".to_string()))?;
+
+        Ok(Self {
+            version,
+            flags,
+            fields,
+            record_count,
+            created,
+            producer,
+        })
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), ParserError> {
+        let field_set = Field::ALL
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| self.fields.contains(field))
+            .fold(0, |set, (index, _)| set | 1 << index);
+        writer.write_all(HEADER_MAGIC.as_bytes())?;
+        writer.write_u16::<BigEndian>(self.version)?;
+        writer.write_u16::<BigEndian>(self.flags)?;
+        writer.write_u16::<BigEndian>(field_set)?;
+        writer.write_u64::<BigEndian>(self.record_count.unwrap_or(UNKNOWN_COUNT))?;
+        writer.write_u64::<BigEndian>(self.created)?;
+        writer.write_u16::<BigEndian>(self.producer.len() as u16)?;
+        writer.write_all(self.producer.as_bytes())?;
+
+        Ok(())
+    }
+}
 
 /// The `BinParserFactory` struct is a factory for creating BIN parsers.
 pub struct BinParserFactory;
@@ -18,44 +188,24 @@ impl ParserFactory for BinParserFactory {
     type Parser = BinParser;
 
     fn create_parser(&self) -> Self::Parser {
-        BinParser
+        BinParser::default()
     }
 }
 
-/// The `BinParser` struct is a parser for BIN files.
-pub struct BinParser;
+/// The `BinParser` struct is a parser for BIN files, reading both versions and writing the
+/// selected one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BinParser {
+    version: BinVersion,
+    checksums: bool,
+}
 
 impl Parser for BinParser {
     fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
     where
         Self: Sized,
     {
-        let mut magic = [0x00; 4];
-
-        let mut transactions: Vec<Transaction> = vec![];
-
-        loop {
-            let result = r.read_exact(&mut magic);
-
-            if let Err(e) = result {
-                if e.kind() == UnexpectedEof {
-                    break;
-                }
-                return Err(e.into());
-            }
-
-            if String::from_utf8_lossy(&magic) != MAGIC {
-                return Err(InvalidBinFormat("Wrong MAGIC sequence".to_string()));
-            }
-
-            let _ = r.read_u32::<BigEndian>()?;
-
-            let transaction = BinParser::process_transaction(r)?;
-
-            transactions.push(transaction);
-        }
-
-        Ok(transactions)
+        self.read_iter(r).collect()
     }
 
     fn write_to<W: Write>(
@@ -63,42 +213,214 @@ impl Parser for BinParser {
         writer: &mut W,
         transactions: &[Transaction],
     ) -> Result<(), ParserError> {
-        for transaction in transactions {
-            Self::serialize_transaction(transaction, writer)?;
-        }
+        self.write_records(
+            writer,
+            transactions.iter().map(Ok),
+            Some(transactions.len() as u64),
+            None,
+        )?;
+        Ok(())
+    }
 
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut records = BinRecords::new(r);
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = records.next_record().transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions, None, None)?;
         Ok(())
     }
 }
 
 impl BinParser {
-    fn process_transaction<R: Read>(r: &mut R) -> Result<Transaction, ParserError> {
-        let mut transaction = Transaction::default();
+    /// Creates a parser writing files of the given version, with a CRC32C of every record and a
+    /// SHA-256 of the file when `checksums` is set, which requires BIN v2.
+    pub fn new(version: BinVersion, checksums: bool) -> Self {
+        Self { version, checksums }
+    }
+
+    /// Reads the header of a BIN file, which is `None` for a v1 file.
+    pub fn read_header<R: Read>(r: &mut R) -> Result<Option<BinHeader>, ParserError> {
+        match read_magic(r)? {
+            Some(magic) if magic == HEADER_MAGIC.as_bytes() => BinHeader::read_from(r).map(Some),
+            Some(magic) if magic == MAGIC.as_bytes() => Ok(None),
+            Some(_) => Err(InvalidBinFormat("Wrong MAGIC sequence".to_string())),
+            None => Ok(None),
+        }
+    }
 
-        transaction.tx_id = r.read_u64::<BigEndian>()?;
-        transaction.tx_type = TransactionType::from_repr((r.read_u8()?) as usize)
+    fn process_transaction<R: Read>(r: &mut R) -> Result<Transaction, ParserError> {
+        let tx_id = r.read_u64::<BigEndian>()?;
+        let tx_type = TransactionType::from_repr((r.read_u8()?) as usize)
             .ok_or(InvalidBinFormat("Wrong transaction type".to_string()))?;
-        transaction.from_user_id = r.read_u64::<BigEndian>()?;
-        transaction.to_user_id = r.read_u64::<BigEndian>()?;
-        transaction.amount = r.read_u64::<BigEndian>()?;
-        transaction.timestamp = r.read_u64::<BigEndian>()?;
-        transaction.status = TransactionStatus::from_repr((r.read_u8()?) as usize)
+        let from_user_id = r.read_u64::<BigEndian>()?;
+        let to_user_id = r.read_u64::<BigEndian>()?;
+        let amount = r.read_u64::<BigEndian>()?;
+        let timestamp = r.read_u64::<BigEndian>()?;
+        let status = TransactionStatus::from_repr((r.read_u8()?) as usize)
             .ok_or(InvalidBinFormat("Wrong transaction status".to_string()))?;
         let description_length = r.read_u32::<BigEndian>()?;
         let mut description = vec![0x00; description_length as usize];
         r.read_exact(&mut description)?;
         let description = String::from_utf8(description)
             .map_err(|_| InvalidBinFormat("Wrong description".to_string()))?;
-        transaction.description = description;
+
+        let transaction = Transaction::new(
+            tx_id,
+            tx_type,
+            from_user_id,
+            to_user_id,
+            amount,
+            timestamp,
+            status,
+            description,
+        );
 
         Ok(transaction)
     }
 
+    /// Writes transactions one at a time to a file, seeking back to fill in the record count of
+    /// a v2 header once all records are written.
+    pub fn write_file<F: Read + Write + Seek>(
+        &self,
+        file: &mut F,
+        transactions: impl IntoIterator<Item = Result<Transaction, ParserError>>,
+    ) -> Result<(), ParserError> {
+        self.write_counted(file, transactions, None)?;
+        Ok(())
+    }
+
+    /// Writes transactions one at a time to a file like `write_file`, and an index of the written
+    /// file to `index_writer`.
+    pub fn write_indexed<F: Read + Write + Seek, X: Write>(
+        &self,
+        file: &mut F,
+        index_writer: &mut X,
+        transactions: impl IntoIterator<Item = Result<Transaction, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut index = IndexWriter::new(BinIndex::DEFAULT_BLOCK_SIZE, ID_BUFFER_SIZE)?;
+        let file_size = self.write_counted(file, transactions, Some(&mut index))?;
+        index.finish(file_size, index_writer)?;
+        index_writer.flush()?;
+        Ok(())
+    }
+
+    fn write_counted<F: Read + Write + Seek>(
+        &self,
+        file: &mut F,
+        transactions: impl IntoIterator<Item = Result<Transaction, ParserError>>,
+        index: Option<&mut IndexWriter>,
+    ) -> Result<u64, ParserError> {
+        let start = file.stream_position()?;
+        let mut writer = BufWriter::new(&mut *file);
+        let count = self.write_records(&mut writer, transactions, None, index)?;
+        writer.flush()?;
+        drop(writer);
+
+        if self.version == BinVersion::V2 {
+            let end = file.stream_position()?;
+            file.seek(SeekFrom::Start(start + RECORD_COUNT_OFFSET))?;
+            file.write_u64::<BigEndian>(count)?;
+            // The SHA-256 in the trailer covers the header, so it is computed again.
+            if self.checksums {
+                file.seek(SeekFrom::Start(start))?;
+                let mut hashing = Hashing::new((&mut *file).take(end - start - 32), true);
+                std::io::copy(&mut hashing, &mut std::io::sink())?;
+                let Hashing { hasher, .. } = hashing;
+                if let Some(hasher) = hasher {
+                    file.write_all(&hasher.finalize())?;
+                }
+            }
+            file.seek(SeekFrom::Start(end))?;
+        }
+
+        Ok(file.stream_position()? - start)
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+        record_count: Option<u64>,
+        mut index: Option<&mut IndexWriter>,
+    ) -> Result<u64, ParserError> {
+        if self.checksums && self.version == BinVersion::V1 {
+            return Err(ParserError::InvalidValue(
+                "Checksums require BIN version 2".to_string(),
+            ));
+        }
+
+        let mut writer = Hashing::new(writer, self.checksums);
+        let mut buffer = vec![];
+        if self.version == BinVersion::V2 {
+            let flags = if self.checksums {
+                BinHeader::RECORD_CRC32C | BinHeader::FILE_SHA256
+            } else {
+                0
+            };
+            let header = BinHeader::new(record_count, flags);
+            header.write_to(&mut buffer)?;
+            writer.write_all(&buffer)?;
+            if let Some(index) = index.as_deref_mut() {
+                index.set_created(header.created);
+            }
+        }
+
+        let mut offset = buffer.len() as u64;
+        let mut count = 0u64;
+        for transaction in transactions {
+            let transaction = transaction?;
+            buffer.clear();
+            Self::serialize_transaction(transaction.borrow(), &mut buffer)?;
+            if self.checksums {
+                let crc = crc32c::crc32c(&buffer);
+                buffer.write_u32::<BigEndian>(crc)?;
+            }
+            writer.write_all(&buffer)?;
+            if let Some(index) = index.as_deref_mut() {
+                let transaction = transaction.borrow();
+                index.push(offset, transaction.tx_id, transaction.timestamp)?;
+            }
+            offset += buffer.len() as u64;
+            count += 1;
+        }
+
+        if self.version == BinVersion::V2 {
+            writer.write_all(TRAILER_MAGIC.as_bytes())?;
+            writer.write_u64::<BigEndian>(count)?;
+            if let Some(hasher) = writer.hasher.take() {
+                writer.inner.write_all(&hasher.finalize())?;
+            }
+        }
+
+        Ok(count)
+    }
+
     fn serialize_transaction<W: Write>(
         transaction: &Transaction,
         writer: &mut W,
     ) -> Result<(), ParserError> {
-        let record_size = 42 + transaction.description.len();
+        let record_size = 46 + transaction.description.len();
         writer.write_all(MAGIC.as_bytes())?;
         writer.write_u32::<BigEndian>(record_size as u32)?;
         writer.write_u64::<BigEndian>(transaction.tx_id)?;
@@ -108,21 +430,205 @@ impl BinParser {
         writer.write_u64::<BigEndian>(transaction.amount)?;
         writer.write_u64::<BigEndian>(transaction.timestamp)?;
         writer.write_u8(transaction.status as u8)?;
+        writer.write_u32::<BigEndian>(transaction.description.len() as u32)?;
         writer.write_all(transaction.description.as_bytes())?;
 
         Ok(())
     }
 }
 
+/// The `BinRecords` struct reads the records of a BIN file of either version.
+struct BinRecords<R> {
+    r: Hashing<R>,
+    /// Whether the first magic sequence of the file was read.
+    started: bool,
+    header: Option<BinHeader>,
+    /// The number of records read.
+    count: u64,
+    /// Whether the trailer of a v2 file was read.
+    finished: bool,
+}
+
+impl<R: Read> BinRecords<R> {
+    fn new(r: R) -> Self {
+        Self {
+            r: Hashing::new(r, false),
+            started: false,
+            header: None,
+            count: 0,
+            finished: false,
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Transaction>, ParserError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let Some(mut magic) = read_magic(&mut self.r)? else {
+            return match self.header {
+                Some(_) => Err(InvalidBinFormat("Missing trailer".to_string())),
+                None => Ok(None),
+            };
+        };
+        if !self.started {
+            self.started = true;
+            if magic == HEADER_MAGIC.as_bytes() {
+                let header = BinHeader::read_from(&mut self.r)?;
+                if header.flags & BinHeader::FILE_SHA256 != 0 {
+                    // The header is hashed as written, which its serialization reproduces.
+                    let mut bytes = vec![];
+                    header.write_to(&mut bytes)?;
+                    self.r.hasher = Some(Sha256::new_with_prefix(bytes));
+                }
+                self.header = Some(header);
+                magic = read_magic(&mut self.r)?
+                    .ok_or(InvalidBinFormat("Missing trailer".to_string()))?;
+            }
+        }
+
+        if magic == MAGIC.as_bytes() {
+            let crc = self
+                .header
+                .as_ref()
+                .is_some_and(|header| header.flags & BinHeader::RECORD_CRC32C != 0);
+            let transaction = read_record(&mut self.r, magic, crc, self.count + 1)?;
+            self.count += 1;
+            return Ok(Some(transaction));
+        }
+        match &self.header {
+            Some(header) if magic == TRAILER_MAGIC.as_bytes() => {
+                let count = self.r.read_u64::<BigEndian>()?;
+                if let Some(hasher) = self.r.hasher.take() {
+                    let mut expected = [0x00; 32];
+                    self.r.read_exact(&mut expected)?;
+                    if hasher.finalize()[..] != expected {
+                        return Err(ParserError::ChecksumMismatch(
+                            "SHA-256 of the file does not match its trailer".to_string(),
+                        ));
+                    }
+                }
+                if count != self.count || header.record_count.is_some_and(|n| n != count) {
+                    return Err(InvalidBinFormat(format!(
+                        "Read {} records, but the file has {}",
+                        self.count, count
+                    )));
+                }
+                if self.r.read(&mut [0x00])? != 0 {
+                    return Err(InvalidBinFormat("Data after trailer".to_string()));
+                }
+                self.finished = true;
+                Ok(None)
+            }
+            _ => Err(InvalidBinFormat("Wrong MAGIC sequence".to_string())),
+        }
+    }
+}
+
+/// The `Hashing` struct computes the SHA-256 of the bytes read from or written to a stream,
+/// while it has a hasher.
+struct Hashing<S> {
+    inner: S,
+    hasher: Option<Sha256>,
+}
+
+impl<S> Hashing<S> {
+    fn new(inner: S, hash: bool) -> Self {
+        Self {
+            inner,
+            hasher: hash.then(Sha256::new),
+        }
+    }
+}
+
+impl<R: Read> Read for Hashing<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Hashing<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads a record following its magic sequence, verifying the CRC32C after it when `crc` is set.
+/// `number` is the number of the record in the file, counted from 1, for errors.
+pub(crate) fn read_record<R: Read>(
+    r: &mut R,
+    magic: [u8; 4],
+    crc: bool,
+    number: u64,
+) -> Result<Transaction, ParserError> {
+    if !crc {
+        let _ = r.read_u32::<BigEndian>()?;
+        return BinParser::process_transaction(r);
+    }
+
+    let record_size = r.read_u32::<BigEndian>()?;
+    let mut record = magic.to_vec();
+    record.write_u32::<BigEndian>(record_size)?;
+    r.take(record_size as u64).read_to_end(&mut record)?;
+    if record.len() < 8 + record_size as usize {
+        return Err(InvalidBinFormat(format!("Record {} is truncated", number)));
+    }
+
+    let expected = r.read_u32::<BigEndian>()?;
+    let crc = crc32c::crc32c(&record);
+    if crc != expected {
+        return Err(ParserError::ChecksumMismatch(format!(
+            "CRC32C of record {} is {:08x}, expected {:08x}",
+            number, crc, expected
+        )));
+    }
+
+    let mut fields = &record[8..];
+    let transaction = BinParser::process_transaction(&mut fields)?;
+    if !fields.is_empty() {
+        return Err(InvalidBinFormat(format!("Wrong size of record {}", number)));
+    }
+    Ok(transaction)
+}
+
+/// Reads a magic sequence, which is `None` at the end of the file.
+pub(crate) fn read_magic<R: Read>(r: &mut R) -> Result<Option<[u8; 4]>, ParserError> {
+    let mut magic = [0x00; 4];
+
+    let result = r.read_exact(&mut magic);
+
+    if let Err(e) = result {
+        if e.kind() == UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(e.into());
+    }
+
+    Ok(Some(magic))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs::{File, OpenOptions};
+    use std::io::Cursor;
 
     #[test]
     fn it_works() {
         let mut file = File::open("../samples/records_example.bin").unwrap();
-        let res = BinParser.read_from(&mut file);
+        let res = BinParser::default().read_from(&mut file);
         assert!(res.is_ok());
         let res = res.unwrap();
         assert_eq!(res.len(), 1000);
@@ -136,11 +642,188 @@ mod tests {
             .create_new(true)
             .open("../samples/records_example_out.bin")
             .unwrap();
-        let transactions = BinParser
+        let transactions = BinParser::default()
             .read_from(&mut File::open("../samples/records_example.bin").unwrap())
             .unwrap();
-        assert!(BinParser.write_to(&mut file, &transactions).is_ok());
-        assert!(BinParser.read_from(&mut file).is_ok());
+        assert!(
+            BinParser::default()
+                .write_to(&mut file, &transactions)
+                .is_ok()
+        );
+        assert!(BinParser::default().read_from(&mut file).is_ok());
         std::fs::remove_file("../samples/records_example_out.bin").unwrap();
     }
+
+    #[test]
+    fn it_reads_written_bin() {
+        let transactions = BinParser::default()
+            .read_from(&mut File::open("../samples/records_example.bin").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        BinParser::default()
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        assert_eq!(
+            BinParser::default()
+                .read_from(&mut buffer.as_slice())
+                .unwrap(),
+            transactions
+        );
+    }
+
+    #[test]
+    fn it_writes_bin_records_like_the_sample() {
+        let sample = std::fs::read("../samples/records_example.bin").unwrap();
+        let transactions = BinParser::default()
+            .read_from(&mut sample.as_slice())
+            .unwrap();
+
+        // The record size covers the fields from `tx_id` on, with the description length.
+        let record_size = u32::from_be_bytes(sample[4..8].try_into().unwrap());
+        assert_eq!(record_size as usize, 46 + transactions[0].description.len());
+
+        let mut buffer = vec![];
+        BinParser::default()
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        assert!(buffer == sample);
+    }
+
+    #[test]
+    fn it_reads_written_bin_v2() {
+        let transactions = BinParser::default()
+            .read_from(&mut File::open("../samples/records_example.bin").unwrap())
+            .unwrap();
+        let parser = BinParser::new(BinVersion::V2, false);
+        let mut buffer = vec![];
+        parser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(buffer.starts_with(b"YPBH\x00\x02\x00\x00\x00\xff"));
+        assert_eq!(&buffer[buffer.len() - 12..][..4], b"YPBT");
+
+        let header = BinParser::read_header(&mut buffer.as_slice())
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.fields, Field::ALL);
+        assert_eq!(header.record_count, Some(1000));
+        assert!(header.producer.starts_with("parser "));
+        assert_eq!(
+            parser.read_from(&mut buffer.as_slice()).unwrap(),
+            transactions
+        );
+
+        let mut streamed = vec![];
+        parser
+            .write_iter(&mut streamed, transactions.iter().cloned().map(Ok))
+            .unwrap();
+        let header = BinParser::read_header(&mut streamed.as_slice())
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.record_count, None);
+        assert_eq!(
+            BinParser::default()
+                .read_from(&mut streamed.as_slice())
+                .unwrap(),
+            transactions
+        );
+
+        for parser in [parser, BinParser::new(BinVersion::V2, true)] {
+            let mut file = Cursor::new(vec![]);
+            parser
+                .write_file(&mut file, transactions.iter().cloned().map(Ok))
+                .unwrap();
+            let file = file.into_inner();
+            let header = BinParser::read_header(&mut file.as_slice())
+                .unwrap()
+                .unwrap();
+            assert_eq!(header.record_count, Some(1000));
+            assert_eq!(
+                BinParser::default()
+                    .read_from(&mut file.as_slice())
+                    .unwrap(),
+                transactions
+            );
+        }
+        assert_eq!(
+            BinParser::read_header(&mut File::open("../samples/records_example.bin").unwrap())
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_bin_v2() {
+        let transactions = [Transaction::default(), Transaction::default()];
+        let mut buffer = vec![];
+        BinParser::new(BinVersion::V2, false)
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        let trailer = buffer.len() - 12;
+
+        let mut wrong_version = buffer.clone();
+        wrong_version[5] = 3;
+        let mut wrong_count = buffer.clone();
+        wrong_count[buffer.len() - 1] = 1;
+        let mut data_after_trailer = buffer.clone();
+        data_after_trailer.push(0);
+
+        for bin in [
+            &buffer[..trailer],
+            &buffer[..trailer - 1],
+            &wrong_version,
+            &wrong_count,
+            &data_after_trailer,
+        ] {
+            assert!(
+                BinParser::default()
+                    .read_from(&mut &bin[..])
+                    .is_err_and(|e| matches!(
+                        e,
+                        ParserError::InvalidBinFormat(_) | ParserError::IoError(_)
+                    )),
+            );
+        }
+        assert_eq!(
+            BinParser::default()
+                .read_from(&mut buffer.as_slice())
+                .unwrap(),
+            transactions
+        );
+    }
+
+    #[test]
+    fn it_verifies_bin_checksums() {
+        let transactions = BinParser::default()
+            .read_from(&mut File::open("../samples/records_example.bin").unwrap())
+            .unwrap();
+        let parser = BinParser::new(BinVersion::V2, true);
+        let mut buffer = vec![];
+        parser.write_to(&mut buffer, &transactions).unwrap();
+        assert_eq!(
+            BinParser::default()
+                .read_from(&mut buffer.as_slice())
+                .unwrap(),
+            transactions
+        );
+
+        // A byte of the producer in the header, and of the amount of the first record.
+        let header_size = buffer.windows(4).position(|w| w == b"YPBN").unwrap();
+        for (offset, checksum) in [(header_size - 1, "SHA-256"), (header_size + 40, "CRC32C")] {
+            let mut corrupted = buffer.clone();
+            corrupted[offset] ^= 0x01;
+            assert!(
+                BinParser::default()
+                    .read_from(&mut corrupted.as_slice())
+                    .is_err_and(|e| matches!(
+                        e,
+                        ParserError::ChecksumMismatch(message) if message.starts_with(checksum)
+                    ))
+            );
+        }
+
+        assert!(
+            BinParser::new(BinVersion::V1, true)
+                .write_to(&mut vec![], &transactions)
+                .is_err_and(|e| matches!(e, ParserError::InvalidValue(_)))
+        );
+    }
 }
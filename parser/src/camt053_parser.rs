@@ -0,0 +1,457 @@
+//! The `camt053_parser` module provides functionality to import financial transactions from
+//! ISO 20022 camt.053 bank statements.
+//!
+//! Every `TxDtls` of a statement entry (`Ntry`) becomes a transaction, and an entry without
+//! details becomes a single transaction. Fields are mapped as follows:
+//! - `TX_ID`: the `EndToEndId` reference when it is numeric, otherwise the position of the
+//!   transaction in the statement, starting from 1.
+//! - `TX_TYPE`: `DEPOSIT` for a `CRDT` and `WITHDRAWAL` for a `DBIT` indicator.
+//! - `FROM_USER_ID`, `TO_USER_ID`: always 0, accounts are kept in the statement only.
+//! - `AMOUNT`: the transaction amount, or the entry amount, in minor units of its `Ccy`
+//!   currency by ISO 4217, e.g. cents of `EUR`, yen of `JPY` and fils of `KWD`, and in
+//!   hundredths without a currency.
+//! - `TIMESTAMP`: the booking date, or the value date, in milliseconds since the Unix epoch.
+//! - `STATUS`: `SUCCESS` for a `BOOK` entry, `PENDING` for `PDNG`, `INFO` and `FUTR`.
+//! - `DESCRIPTION`: the remittance information followed by the original references, e.g.
+//!   `Invoice 42; NtryRef=E-0001; AcctSvcrRef=BANK-REF-1; EndToEndId=1000000000000001`.
+//!
+//! Statements are read one entry at a time. Writing camt.053 is not supported.
+
+use crate::date::parse_iso_date_time;
+use crate::decimal::parse_minor_units;
+use crate::error::ParserError;
+use crate::transaction::{Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// The `Camt053ParserFactory` struct is a factory for creating camt.053 parsers.
+pub struct Camt053ParserFactory;
+
+impl ParserFactory for Camt053ParserFactory {
+    type Parser = Camt053Parser;
+
+    fn create_parser(&self) -> Self::Parser {
+        Camt053Parser
+    }
+}
+
+/// The `Camt053Parser` struct is a parser for camt.053 bank statements.
+pub struct Camt053Parser;
+
+impl Parser for Camt053Parser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        _writer: &mut W,
+        _transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Err(ParserError::UnsupportedWrite("camt053".to_string()))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = Reader::from_reader(BufReader::new(r));
+        let mut pending = VecDeque::new();
+        let mut position = 0;
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            while pending.is_empty() {
+                match Self::read_entry(&mut reader) {
+                    Ok(Some(entry)) => match Self::map_entry(&entry, &mut position) {
+                        Ok(transactions) => pending.extend(transactions),
+                        Err(e) => {
+                            failed = true;
+                            return Some(Err(e));
+                        }
+                    },
+                    Ok(None) => return None,
+                    Err(e) => {
+                        failed = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+            pending.pop_front().map(Ok)
+        }))
+    }
+}
+
+impl Camt053Parser {
+    /// Reads events up to the end of the next `Ntry` element.
+    fn read_entry<B: BufRead>(reader: &mut Reader<B>) -> Result<Option<Element>, ParserError> {
+        let mut buffer = vec![];
+        loop {
+            buffer.clear();
+            match reader.read_event_into(&mut buffer).map_err(camt053_error)? {
+                Event::Start(e) if e.local_name().as_ref() == b"Ntry" => {
+                    let start = e.into_owned();
+                    return Element::read(reader, &start).map(Some);
+                }
+                Event::Eof => return Ok(None),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Maps an entry to one transaction per `TxDtls`, or a single transaction without details.
+    fn map_entry(entry: &Element, position: &mut u64) -> Result<Vec<Transaction>, ParserError> {
+        let details = entry
+            .find(&["NtryDtls"])
+            .map(|details| details.children("TxDtls").collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let status = entry
+            .find(&["Sts"])
+            .map(|status| status.find(&["Cd"]).unwrap_or(status).text.as_str());
+        let status = match status {
+            Some("BOOK") => TransactionStatus::Success,
+            Some("PDNG" | "INFO" | "FUTR") => TransactionStatus::Pending,
+            status => {
+                return Err(ParserError::InvalidCamt053Format(format!(
+                    "Unknown entry status: {}",
+                    status.unwrap_or_default()
+                )));
+            }
+        };
+        let date = entry
+            .find(&["BookgDt"])
+            .or(entry.find(&["ValDt"]))
+            .and_then(|date| date.find(&["DtTm"]).or(date.find(&["Dt"])))
+            .ok_or(ParserError::InvalidCamt053Format(
+                "Missing booking and value date".to_string(),
+            ))?;
+        let timestamp = parse_iso_date_time(&date.text).ok_or_else(|| {
+            ParserError::InvalidCamt053Format(format!("Invalid date: {}", date.text))
+        })?;
+
+        let entry_references = ["NtryRef", "AcctSvcrRef"]
+            .into_iter()
+            .filter_map(|name| entry.find(&[name]))
+            .collect::<Vec<_>>();
+
+        let mut transactions = vec![];
+        for detail in details.iter().copied().map(Some).chain(
+            // An entry without details is a transaction of its own.
+            details.is_empty().then_some(None),
+        ) {
+            *position += 1;
+
+            let amount = detail
+                .and_then(|detail| {
+                    detail
+                        .find(&["AmtDtls", "TxAmt", "Amt"])
+                        .or(detail.find(&["Amt"]))
+                })
+                .or(entry.find(&["Amt"]))
+                .ok_or(ParserError::InvalidCamt053Format(
+                    "Missing amount".to_string(),
+                ))?;
+            let indicator = detail
+                .and_then(|detail| detail.find(&["CdtDbtInd"]))
+                .or(entry.find(&["CdtDbtInd"]));
+            let tx_type = match indicator.map(|indicator| indicator.text.as_str()) {
+                Some("CRDT") => TransactionType::Deposit,
+                Some("DBIT") => TransactionType::Withdrawal,
+                indicator => {
+                    return Err(ParserError::InvalidCamt053Format(format!(
+                        "Unknown credit/debit indicator: {}",
+                        indicator.unwrap_or_default()
+                    )));
+                }
+            };
+
+            let references = detail
+                .and_then(|detail| detail.find(&["Refs"]))
+                .map(|references| references.children.iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+            let tx_id = references
+                .iter()
+                .find(|reference| reference.name == "EndToEndId")
+                .and_then(|reference| reference.text.parse::<u64>().ok())
+                .unwrap_or(*position);
+
+            let remittance = detail
+                .and_then(|detail| detail.find(&["RmtInf"]))
+                .map(|remittance| {
+                    remittance
+                        .children("Ustrd")
+                        .map(|text| text.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .or(detail
+                    .and_then(|detail| detail.find(&["AddtlTxInf"]))
+                    .map(|text| text.text.clone()))
+                .or(entry.find(&["AddtlNtryInf"]).map(|text| text.text.clone()));
+            let description = remittance
+                .into_iter()
+                .chain(
+                    entry_references
+                        .iter()
+                        .chain(references.iter())
+                        .map(|reference| format!("{}={}", reference.name, reference.text)),
+                )
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            transactions.push(Transaction::new(
+                tx_id,
+                tx_type,
+                0,
+                0,
+                parse_amount(amount)?,
+                timestamp,
+                status,
+                description,
+            ));
+        }
+
+        Ok(transactions)
+    }
+}
+
+/// Parses an `Amt` element into minor units of its currency.
+fn parse_amount(amount: &Element) -> Result<u64, ParserError> {
+    let digits = match amount.attribute("Ccy") {
+        Some(currency) => minor_unit_digits(currency).ok_or_else(|| {
+            ParserError::InvalidCamt053Format(format!("Unknown currency: {}", currency))
+        })?,
+        None => 2,
+    };
+    parse_minor_units(&amount.text, '.', digits).ok_or_else(|| {
+        ParserError::InvalidCamt053Format(format!("Invalid amount: {}", amount.text))
+    })
+}
+
+/// Returns the number of minor unit digits of an ISO 4217 currency code.
+fn minor_unit_digits(currency: &str) -> Option<u32> {
+    if currency.len() != 3 || !currency.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        "CLF" | "UYW" => 4,
+        _ => 2,
+    })
+}
+
+/// The `Element` struct is an XML element read into memory, without namespaces.
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    /// Creates an empty element with the name and attributes of a start tag.
+    fn new(start: &BytesStart) -> Result<Self, ParserError> {
+        let attributes = start
+            .attributes()
+            .map(|attribute| {
+                let attribute = attribute.map_err(camt053_error)?;
+                let value = attribute.unescape_value().map_err(camt053_error)?;
+                Ok((
+                    String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned(),
+                    value.into_owned(),
+                ))
+            })
+            .collect::<Result<Vec<_>, ParserError>>()?;
+        Ok(Element {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+            attributes,
+            text: String::new(),
+            children: vec![],
+        })
+    }
+
+    /// Reads the content of an element up to its end tag.
+    fn read<B: BufRead>(reader: &mut Reader<B>, start: &BytesStart) -> Result<Self, ParserError> {
+        let mut element = Element::new(start)?;
+        let mut buffer = vec![];
+        loop {
+            buffer.clear();
+            match reader.read_event_into(&mut buffer).map_err(camt053_error)? {
+                Event::Start(e) => {
+                    let start = e.into_owned();
+                    element.children.push(Element::read(reader, &start)?);
+                }
+                Event::Empty(e) => element.children.push(Element::new(&e)?),
+                Event::Text(e) => element.text += &e.decode().map_err(camt053_error)?,
+                Event::CData(e) => element.text += &e.decode().map_err(camt053_error)?,
+                Event::GeneralRef(e) => {
+                    let reference = format!("&{};", e.decode().map_err(camt053_error)?);
+                    element.text +=
+                        &quick_xml::escape::unescape(&reference).map_err(camt053_error)?;
+                }
+                Event::End(_) => {
+                    element.text = element.text.trim().to_string();
+                    return Ok(element);
+                }
+                Event::Eof => {
+                    return Err(ParserError::InvalidCamt053Format(format!(
+                        "Unexpected end of file in {}",
+                        element.name
+                    )));
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Returns the value of the attribute with the given name.
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the child elements with the given name.
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Returns the first descendant at the given path of element names.
+    fn find(&self, path: &[&str]) -> Option<&Element> {
+        let mut element = self;
+        for name in path {
+            element = element.children.iter().find(|child| child.name == *name)?;
+        }
+        Some(element)
+    }
+}
+
+fn camt053_error(e: impl Display) -> ParserError {
+    ParserError::InvalidCamt053Format(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_camt053() {
+        let mut file = File::open("../samples/camt053_example.xml").unwrap();
+        let transactions = Camt053Parser.read_from(&mut file).unwrap();
+        assert_eq!(transactions.len(), 4);
+
+        assert_eq!(transactions[0].tx_id, 1000000000000001);
+        assert_eq!(transactions[0].tx_type, TransactionType::Deposit);
+        assert_eq!(transactions[0].amount, 10025);
+        assert_eq!(transactions[0].timestamp, 1633073400000);
+        assert_eq!(transactions[0].status, TransactionStatus::Success);
+        assert_eq!(
+            transactions[0].description,
+            "Invoice 42; NtryRef=E-0001; AcctSvcrRef=BANK-REF-1; \
+             EndToEndId=1000000000000001; TxId=TX-A"
+        );
+
+        assert_eq!(transactions[1].tx_id, 2);
+        assert_eq!(transactions[1].amount, 5000);
+        assert!(transactions[1].description.starts_with("Invoice 43 & 44; "));
+
+        assert_eq!(transactions[2].tx_type, TransactionType::Withdrawal);
+        assert_eq!(transactions[2].status, TransactionStatus::Pending);
+        assert_eq!(transactions[2].amount, 2050);
+        assert_eq!(transactions[2].description, "Card payment; NtryRef=E-0002");
+
+        assert_eq!(transactions[3].tx_id, 1000000000000003);
+        assert_eq!(transactions[3].timestamp, 1633132800000);
+        assert_eq!(
+            transactions[3].description,
+            "Account fee; InstrId=I-7; EndToEndId=1000000000000003"
+        );
+    }
+
+    #[test]
+    fn it_reads_camt053_amounts_in_minor_units() {
+        let entry = |amount: &str| {
+            format!(
+                "<Document><BkToCstmrStmt><Stmt><Ntry>{}<CdtDbtInd>CRDT</CdtDbtInd>\
+                 <Sts>BOOK</Sts><ValDt><Dt>2021-10-01</Dt></ValDt></Ntry></Stmt></BkToCstmrStmt>\
+                 </Document>",
+                amount
+            )
+        };
+        for (amount, expected) in [
+            ("<Amt Ccy=\"EUR\">100.25</Amt>", 10025),
+            ("<Amt Ccy=\"JPY\">1000</Amt>", 1000),
+            ("<Amt Ccy=\"KWD\">1.234</Amt>", 1234),
+            ("<Amt>7.5</Amt>", 750),
+        ] {
+            let transactions = Camt053Parser
+                .read_from(&mut entry(amount).as_bytes())
+                .unwrap();
+            assert_eq!(transactions[0].amount, expected, "{}", amount);
+        }
+        for amount in [
+            "<Amt Ccy=\"JPY\">1000.5</Amt>",
+            "<Amt Ccy=\"EUR\">1.234</Amt>",
+            "<Amt Ccy=\"euro\">1.00</Amt>",
+        ] {
+            assert!(
+                Camt053Parser
+                    .read_from(&mut entry(amount).as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidCamt053Format(_))),
+                "{}",
+                amount
+            );
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_camt053_format() {
+        let entry = |content: &str| {
+            format!(
+                "<Document><BkToCstmrStmt><Stmt><Ntry>{}</Ntry></Stmt></BkToCstmrStmt></Document>",
+                content
+            )
+        };
+        for xml in [
+            entry("<Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts>"),
+            entry(
+                "<Amt>1.001</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts>BOOK</Sts><ValDt><Dt>2021-10-01</Dt></ValDt>",
+            ),
+            entry(
+                "<Amt>1.00</Amt><CdtDbtInd>RVSL</CdtDbtInd><Sts>BOOK</Sts><ValDt><Dt>2021-10-01</Dt></ValDt>",
+            ),
+            entry(
+                "<Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts>LOST</Sts><ValDt><Dt>2021-10-01</Dt></ValDt>",
+            ),
+            "<Document><Ntry><Amt>1.00".to_string(),
+        ] {
+            assert!(
+                Camt053Parser
+                    .read_from(&mut xml.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidCamt053Format(_))),
+                "{}",
+                xml
+            );
+        }
+        assert!(
+            Camt053Parser
+                .write_to(&mut vec![], &[Transaction::default()])
+                .is_err_and(|e| matches!(e, ParserError::UnsupportedWrite(_)))
+        );
+    }
+}
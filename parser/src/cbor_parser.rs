@@ -0,0 +1,234 @@
+//! The `cbor_parser` module provides functionality to parse financial transactions from CBOR files.
+//!
+//! Every transaction is a map from the lower-case field names to their values, e.g.
+//! `{"tx_id": 1000000000000000, "tx_type": "DEPOSIT", ...}`, and the maps follow each other as
+//! a CBOR sequence (RFC 8742), so transactions are read and written one at a time. Numbers are
+//! unsigned integers and the other fields are text strings. Tags, such as the self-described
+//! CBOR tag, and indefinite-length maps and strings are accepted when reading.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use ciborium_ll::{Decoder, Encoder, Header};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+/// The `CborParserFactory` struct is a factory for creating CBOR parsers.
+pub struct CborParserFactory;
+
+impl ParserFactory for CborParserFactory {
+    type Parser = CborParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        CborParser
+    }
+}
+
+/// The `CborParser` struct is a parser for CBOR files.
+pub struct CborParser;
+
+impl Parser for CborParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = BufReader::new(r);
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut reader).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl CborParser {
+    fn read_record<R: BufRead>(reader: &mut R) -> Result<Option<Transaction>, ParserError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut decoder = Decoder::from(reader);
+        let len = match Self::pull(&mut decoder)? {
+            Header::Map(len) => len,
+            header => return Err(invalid(format!("Expected a map, found {:?}", header))),
+        };
+
+        let mut record = Record::new(invalid);
+        let mut index = 0;
+        while len.is_none_or(|len| index < len) {
+            let key = match Self::pull(&mut decoder)? {
+                Header::Break if len.is_none() => break,
+                Header::Text(len) => Self::read_text(&mut decoder, len)?,
+                header => return Err(invalid(format!("Expected a key, found {:?}", header))),
+            };
+            let field = record.field(&key)?;
+            let value = match (field.is_numeric(), Self::pull(&mut decoder)?) {
+                (true, Header::Positive(number)) => number.to_string(),
+                (false, Header::Text(len)) => Self::read_text(&mut decoder, len)?,
+                (_, header) => {
+                    return Err(invalid(format!(
+                        "Invalid value of {}: {:?}",
+                        field.key(),
+                        header
+                    )));
+                }
+            };
+            record.set(field, &value)?;
+            index += 1;
+        }
+        record.finish().map(Some)
+    }
+
+    /// Pulls the next header, skipping tags.
+    fn pull<R: Read>(decoder: &mut Decoder<R>) -> Result<Header, ParserError> {
+        loop {
+            match decoder.pull().map_err(cbor_error)? {
+                Header::Tag(_) => continue,
+                header => return Ok(header),
+            }
+        }
+    }
+
+    fn read_text<R: Read>(
+        decoder: &mut Decoder<R>,
+        len: Option<usize>,
+    ) -> Result<String, ParserError> {
+        let mut text = String::new();
+        let mut buffer = [0; 4096];
+        let mut segments = decoder.text(len);
+        while let Some(mut segment) = segments.pull().map_err(cbor_error)? {
+            while let Some(chunk) = segment.pull(&mut buffer).map_err(cbor_error)? {
+                text.push_str(chunk);
+            }
+        }
+        Ok(text)
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut encoder = Encoder::from(BufWriter::new(writer));
+        for transaction in transactions {
+            let transaction = transaction?;
+            let transaction = transaction.borrow();
+            encoder.push(Header::Map(Some(Field::ALL.len())))?;
+            for field in Field::ALL {
+                encoder.text(field.key(), None)?;
+                match field.number(transaction) {
+                    Some(number) => encoder.push(Header::Positive(number))?,
+                    None => encoder.text(&field.value(transaction), None)?,
+                }
+            }
+        }
+        ciborium_io::Write::flush(&mut encoder)?;
+        Ok(())
+    }
+}
+
+fn cbor_error(e: ciborium_ll::Error<std::io::Error>) -> ParserError {
+    match e {
+        ciborium_ll::Error::Io(e) => invalid(e.to_string()),
+        ciborium_ll::Error::Syntax(offset) => invalid(format!("Syntax error at {}", offset)),
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidCborFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_cbor() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        CborParser.write_to(&mut buffer, &transactions).unwrap();
+        // A map of 8 entries with the text key "tx_id".
+        assert!(buffer.starts_with(b"\xa8\x65tx_id"));
+
+        let read = CborParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, transactions);
+    }
+
+    #[test]
+    fn it_reads_indefinite_length_cbor() {
+        let mut buffer = vec![];
+        CborParser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+        // The self-described CBOR tag, an indefinite-length map and a chunked key.
+        let mut cbor = b"\xd9\xd9\xf7\xbf\x7f\x63tx_\x62id\xff".to_vec();
+        cbor.extend_from_slice(&buffer[7..]);
+        cbor.push(0xff);
+
+        let read = CborParser.read_from(&mut cbor.as_slice()).unwrap();
+        assert_eq!(read, [Transaction::default()]);
+    }
+
+    #[test]
+    fn it_is_invalid_cbor_format() {
+        let mut buffer = vec![];
+        CborParser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+        let mut negative = buffer.clone();
+        // The tx_id 0 is the unsigned integer 0x00, -1 is the negative integer 0x20.
+        negative[7] = 0x20;
+
+        for cbor in [
+            b"\x81\x01".as_slice(),
+            &buffer[..buffer.len() - 1],
+            &negative,
+            b"\xa1\x65TX_ID\x01",
+            b"\xa1\x65tx_id\x01",
+        ] {
+            assert!(
+                CborParser
+                    .read_from(&mut &cbor[..])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidCborFormat(_))),
+                "{:?}",
+                cbor
+            );
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! The `compression` module provides transparent compression of transaction files.
+//!
+//! Compressed input is detected by its magic bytes, so any format can be read from a
+//! gzip, zstd or xz stream. Compressed output is selected by the file extension
+//! (`.gz`, `.zst` or `.xz`), e.g. `records.csv.gz`.
+
+use flate2::Compression as GzipLevel;
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use xz2::bufread::XzDecoder;
+use xz2::write::XzEncoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// The `Compression` enum represents a compression codec.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Compression {
+    /// No compression.
+    None,
+    /// The gzip codec.
+    Gzip,
+    /// The zstd codec.
+    Zstd,
+    /// The xz codec.
+    Xz,
+}
+
+impl Compression {
+    /// Detects the compression from the extension of a path.
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("gz") => Compression::Gzip,
+            Some(e) if e.eq_ignore_ascii_case("zst") => Compression::Zstd,
+            Some(e) if e.eq_ignore_ascii_case("xz") => Compression::Xz,
+            _ => Compression::None,
+        }
+    }
+
+    /// Detects the compression from the magic bytes at the start of a reader.
+    pub fn detect<R: BufRead>(r: &mut R) -> std::io::Result<Compression> {
+        let buffer = r.fill_buf()?;
+        Ok(if buffer.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if buffer.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if buffer.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else {
+            Compression::None
+        })
+    }
+
+    /// Returns the file extension of the codec.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+            Compression::Xz => Some("xz"),
+        }
+    }
+
+    /// Returns the path without its compression extension, e.g. `records.csv` for `records.csv.gz`.
+    pub fn strip_extension(path: &Path) -> PathBuf {
+        match Compression::from_path(path) {
+            Compression::None => path.to_path_buf(),
+            _ => path.with_extension(""),
+        }
+    }
+
+    /// Wraps a reader with a decoder for the codec detected from its magic bytes.
+    pub fn decoder<'a, R: BufRead + 'a>(mut r: R) -> std::io::Result<Box<dyn Read + 'a>> {
+        Ok(match Compression::detect(&mut r)? {
+            Compression::None => Box::new(r),
+            Compression::Gzip => Box::new(MultiGzDecoder::new(r)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(r)?),
+            Compression::Xz => Box::new(XzDecoder::new_multi_decoder(r)),
+        })
+    }
+
+    /// Wraps a writer with an encoder for the codec.
+    pub fn encoder<W: Write>(&self, w: W) -> std::io::Result<CompressedWriter<W>> {
+        Ok(match self {
+            Compression::None => CompressedWriter::Plain(w),
+            Compression::Gzip => CompressedWriter::Gzip(GzEncoder::new(w, GzipLevel::default())),
+            Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(w, 0)?),
+            Compression::Xz => CompressedWriter::Xz(XzEncoder::new(w, 6)),
+        })
+    }
+
+    /// Opens a file for reading, decompressing it if needed.
+    pub fn open(path: &Path) -> std::io::Result<Box<dyn Read>> {
+        Compression::decoder(BufReader::new(File::open(path)?))
+    }
+
+    /// Creates a file for writing, compressing it according to its extension.
+    pub fn create(path: &Path) -> std::io::Result<CompressedWriter<BufWriter<File>>> {
+        Compression::from_path(path).encoder(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// The `CompressedWriter` enum is a writer that compresses data with a codec.
+///
+/// `finish` must be called to write the end of the compressed stream.
+pub enum CompressedWriter<W: Write> {
+    /// A writer without compression.
+    Plain(W),
+    /// A gzip writer.
+    Gzip(GzEncoder<W>),
+    /// A zstd writer.
+    Zstd(zstd::Encoder<'static, W>),
+    /// An xz writer.
+    Xz(XzEncoder<W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Writes the end of the compressed stream, flushes and returns the inner writer.
+    pub fn finish(self) -> std::io::Result<W> {
+        let mut w = match self {
+            CompressedWriter::Plain(w) => w,
+            CompressedWriter::Gzip(w) => w.finish()?,
+            CompressedWriter::Zstd(w) => w.finish()?,
+            CompressedWriter::Xz(w) => w.finish()?,
+        };
+        w.flush()?;
+        Ok(w)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write(buf),
+            CompressedWriter::Gzip(w) => w.write(buf),
+            CompressedWriter::Zstd(w) => w.write(buf),
+            CompressedWriter::Xz(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.flush(),
+            CompressedWriter::Gzip(w) => w.flush(),
+            CompressedWriter::Zstd(w) => w.flush(),
+            CompressedWriter::Xz(w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvParserFactory, Parser, ParserFactory};
+
+    #[test]
+    fn it_detects_compression_from_path() {
+        assert_eq!(
+            Compression::from_path(Path::new("records.csv.gz")),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::from_path(Path::new("records.txt.zst")),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::from_path(Path::new("records.bin")),
+            Compression::None
+        );
+        assert_eq!(
+            Compression::strip_extension(Path::new("dir/records.bin.xz")),
+            Path::new("dir/records.bin")
+        );
+    }
+
+    #[test]
+    fn it_reads_compressed_csv() {
+        let parser = CsvParserFactory.create_parser();
+        let transactions = parser
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Zstd,
+            Compression::Xz,
+        ] {
+            let mut writer = compression.encoder(vec![]).unwrap();
+            parser.write_to(&mut writer, &transactions).unwrap();
+            let compressed = writer.finish().unwrap();
+
+            let mut reader = Compression::decoder(compressed.as_slice()).unwrap();
+            assert_eq!(parser.read_from(&mut reader).unwrap(), transactions);
+        }
+    }
+}
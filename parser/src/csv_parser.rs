@@ -1,13 +1,87 @@
 //! The `csv_parser` module provides functionality to parse financial transactions from CSV files.
+//!
+//! By default all transaction fields are stored in the order of the standard header. A
+//! `CsvColumns` mapping selects, reorders and renames the columns, e.g.
+//! `tx_id,amount,from_user_id=payer`; fields without a column are left at their default
+//! values when reading.
 
 use crate::error::ParserError;
-use crate::transaction::{Transaction, TransactionStatus, TransactionType};
-use crate::{Parser, ParserFactory};
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
 
-const CSV_HEADER: &str =
-    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
+/// The `CsvColumns` struct maps transaction fields to named CSV columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvColumns {
+    columns: Vec<(Field, String)>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            columns: Field::ALL
+                .iter()
+                .map(|field| (*field, field.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for CsvColumns {
+    type Err = ParserError;
+
+    /// Parses columns separated by commas or new lines, each as `field` or `field=name`.
+    /// Lines starting with `#` are comments, so a mapping can be kept in a file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut columns: Vec<(Field, String)> = vec![];
+
+        let items = s
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|item| !item.is_empty());
+
+        for item in items {
+            let (field, name) = match item.split_once('=') {
+                Some((field, name)) => (field.trim().parse::<Field>()?, name.trim().to_string()),
+                None => {
+                    let field = item.parse::<Field>()?;
+                    (field, field.to_string())
+                }
+            };
+            if name.is_empty() {
+                return Err(ParserError::InvalidCsvColumns(item.to_string()));
+            }
+            if columns.iter().any(|(f, n)| *f == field || *n == name) {
+                return Err(ParserError::InvalidCsvColumns(format!(
+                    "Duplicate column: {}",
+                    item
+                )));
+            }
+            columns.push((field, name));
+        }
+
+        if columns.is_empty() {
+            return Err(ParserError::InvalidCsvColumns("No columns".to_string()));
+        }
+
+        Ok(Self { columns })
+    }
+}
 
+impl CsvColumns {
+    /// Returns the CSV header line for the columns.
+    pub fn header(&self) -> String {
+        self.columns
+            .iter()
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
 
 /// The `CsvParserFactory` struct is a factory for creating CSV parsers.
 pub struct CsvParserFactory;
@@ -16,49 +90,81 @@ impl ParserFactory for CsvParserFactory {
     type Parser = CsvParser;
 
     fn create_parser(&self) -> Self::Parser {
-        CsvParser
+        CsvParser::default()
     }
 }
 
 /// The `CsvParser` struct is a parser for CSV files.
-pub struct CsvParser;
+#[derive(Default)]
+pub struct CsvParser {
+    columns: CsvColumns,
+}
 
 impl Parser for CsvParser {
     fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
     where
         Self: Sized,
     {
-        let reader = BufReader::new(r);
+        self.read_iter(r).collect()
+    }
 
-        let mut is_first_line = true;
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
 
-        let mut transactions: Vec<Transaction> = vec![];
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut lines = BufReader::new(r).lines();
 
-        for line in reader.lines() {
-            let line = line?;
+        let header = match lines.next() {
+            Some(line) => line
+                .map_err(ParserError::from)
+                .and_then(|line| self.process_header(line)),
+            None => Ok(()),
+        };
 
-            if is_first_line {
-                Self::process_header(line)?;
-                is_first_line = false;
-            } else {
-                let transaction = Self::process_transaction(line)?;
-                transactions.push(transaction);
-            }
+        if let Err(e) = header {
+            return Box::new(std::iter::once(Err(e)));
         }
 
-        Ok(transactions)
+        let columns = self.columns.clone();
+        Box::new(lines.map(move |line| Self::process_transaction(&columns, line?)))
     }
 
-    fn write_to<W: Write>(
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
         &self,
         writer: &mut W,
-        transactions: &[Transaction],
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl CsvParser {
+    /// Creates a parser for CSV files with the given columns.
+    pub fn new(columns: CsvColumns) -> Self {
+        Self { columns }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
     ) -> Result<(), ParserError> {
         let mut buf_writer = BufWriter::new(writer);
-        let header = format!("{}\n", CSV_HEADER);
+        let header = format!("{}\n", self.columns.header());
         buf_writer.write_all(header.as_bytes())?;
         for transaction in transactions {
-            let line = Self::serialize_transaction(transaction);
+            let line = self.serialize_transaction(transaction?.borrow());
             let line = format!("{}\n", line);
             buf_writer.write_all(line.as_bytes())?;
         }
@@ -67,61 +173,38 @@ impl Parser for CsvParser {
 
         Ok(())
     }
-}
 
-impl CsvParser {
-    fn process_header(line: String) -> Result<(), ParserError> {
-        if line != CSV_HEADER {
+    fn process_header(&self, line: String) -> Result<(), ParserError> {
+        if line != self.columns.header() {
             return Err(ParserError::InvalidCsvHeader(line));
         }
         Ok(())
     }
 
-    fn process_transaction(line: String) -> Result<Transaction, ParserError> {
+    fn process_transaction(columns: &CsvColumns, line: String) -> Result<Transaction, ParserError> {
         let parts = &line.split(',').collect::<Vec<&str>>();
 
-        if parts.len() != 8 {
+        if parts.len() != columns.columns.len() {
             return Err(ParserError::InvalidCsvFormat(line));
         }
 
-        Ok(Transaction::new(
-            parts[0]
-                .parse::<u64>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[0].to_string()))?,
-            parts[1]
-                .parse::<TransactionType>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[1].to_string()))?,
-            parts[2]
-                .parse::<u64>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[2].to_string()))?,
-            parts[3]
-                .parse::<u64>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[3].to_string()))?,
-            parts[4]
-                .parse::<u64>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[4].to_string()))?,
-            parts[5]
-                .parse::<u64>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[5].to_string()))?,
-            parts[6]
-                .parse::<TransactionStatus>()
-                .map_err(|_| ParserError::InvalidCsvFormat(parts[6].to_string()))?,
-            parts[7].to_string(),
-        ))
-    }
-
-    fn serialize_transaction(transaction: &Transaction) -> String {
-        format!(
-            "{},{},{},{},{},{},{},{}",
-            transaction.tx_id,
-            transaction.tx_type,
-            transaction.from_user_id,
-            transaction.to_user_id,
-            transaction.amount,
-            transaction.timestamp,
-            transaction.status,
-            transaction.description
-        )
+        let mut transaction = Transaction::default();
+        for ((field, _), part) in columns.columns.iter().zip(parts) {
+            field
+                .set_value(&mut transaction, part)
+                .map_err(|_| ParserError::InvalidCsvFormat(part.to_string()))?;
+        }
+
+        Ok(transaction)
+    }
+
+    fn serialize_transaction(&self, transaction: &Transaction) -> String {
+        self.columns
+            .columns
+            .iter()
+            .map(|(field, _)| field.value(transaction))
+            .collect::<Vec<_>>()
+            .join(",")
     }
 }
 
@@ -133,7 +216,7 @@ mod tests {
     #[test]
     fn it_works() {
         let mut file = File::open("../samples/records_example.csv").unwrap();
-        let res = CsvParser.read_from(&mut file);
+        let res = CsvParser::default().read_from(&mut file);
         assert!(res.is_ok());
         let res = res.unwrap();
         assert_eq!(res.len(), 1000);
@@ -143,7 +226,7 @@ mod tests {
     fn it_is_invalid_csv_header() {
         let mut file = File::open("../samples/records_example_invalid_header.csv").unwrap();
         assert!(
-            CsvParser
+            CsvParser::default()
                 .read_from(&mut file)
                 .is_err_and(|e| matches!(e, ParserError::InvalidCsvHeader(_)))
         );
@@ -153,7 +236,7 @@ mod tests {
     fn it_is_invalid_csv_format() {
         let mut file = File::open("../samples/records_example_invalid_format.csv").unwrap();
         assert!(
-            CsvParser
+            CsvParser::default()
                 .read_from(&mut file)
                 .is_err_and(|e| matches!(e, ParserError::InvalidCsvFormat(_)))
         );
@@ -167,11 +250,51 @@ mod tests {
             .create_new(true)
             .open("../samples/records_example_out.csv")
             .unwrap();
-        let transactions = CsvParser
+        let transactions = CsvParser::default()
             .read_from(&mut File::open("../samples/records_example.csv").unwrap())
             .unwrap();
-        assert!(CsvParser.write_to(&mut file, &transactions).is_ok());
-        assert!(CsvParser.read_from(&mut file).is_ok());
+        assert!(
+            CsvParser::default()
+                .write_to(&mut file, &transactions)
+                .is_ok()
+        );
+        assert!(CsvParser::default().read_from(&mut file).is_ok());
         std::fs::remove_file("../samples/records_example_out.csv").unwrap();
     }
+
+    #[test]
+    fn it_reads_projected_csv() {
+        let columns = "tx_id,amount=value\n# the payer\nFROM_USER_ID = payer"
+            .parse::<CsvColumns>()
+            .unwrap();
+        assert_eq!(columns.header(), "TX_ID,value,payer");
+
+        let parser = CsvParser::new(columns);
+        let transactions = CsvParser::default()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        parser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(buffer.starts_with(b"TX_ID,value,payer\n1000000000000000,100,0\n"));
+
+        let projected = parser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(projected.len(), transactions.len());
+        assert_eq!(projected[1].from_user_id, transactions[1].from_user_id);
+        assert_eq!(projected[1].amount, transactions[1].amount);
+        assert_eq!(projected[1].description, "");
+    }
+
+    #[test]
+    fn it_is_invalid_csv_columns() {
+        for columns in ["", "tx_id,tx_id", "tx_id=a,amount=a", "amount="] {
+            assert!(
+                columns
+                    .parse::<CsvColumns>()
+                    .is_err_and(|e| matches!(e, ParserError::InvalidCsvColumns(_))),
+                "{}",
+                columns
+            );
+        }
+        assert!("unknown".parse::<CsvColumns>().is_err());
+    }
 }
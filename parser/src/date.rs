@@ -0,0 +1,149 @@
+//! The `date` module converts between calendar dates and timestamps in milliseconds since the
+//! Unix epoch.
+
+/// Returns the number of days since 1970-01-01 for a date of the proleptic Gregorian calendar.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the date of the proleptic Gregorian calendar for a number of days since 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats a timestamp in milliseconds since the Unix epoch as an ISO 8601 date (UTC), e.g.
+/// `2021-09-30`.
+pub fn format_iso_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86_400_000) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parses an ISO 8601 date (`2021-10-01`) or date and time (`2021-10-01T10:00:00.5+02:00`).
+///
+/// A time without an offset is taken as UTC. Returns `None` for invalid input or dates
+/// before the Unix epoch.
+pub(crate) fn parse_iso_date_time(s: &str) -> Option<u64> {
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|_| s.bytes().all(|b| b.is_ascii_digit()))
+    };
+
+    let (date, time) = match s.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year = number(parts.next()?)?;
+    let month = number(parts.next()?).filter(|m| (1..=12).contains(m))?;
+    let day = number(parts.next()?).filter(|d| (1..=31).contains(d))?;
+    let mut milliseconds = days_from_civil(i64::from(year), month, day) * 86_400_000;
+
+    if let Some(time) = time {
+        let (time, offset) = match time.find(['Z', '+', '-']) {
+            Some(index) => time.split_at(index),
+            None => (time, ""),
+        };
+        let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let mut parts = time.splitn(3, ':');
+        let hours = number(parts.next()?).filter(|h| *h < 24)?;
+        let minutes = number(parts.next()?).filter(|m| *m < 60)?;
+        let seconds = match parts.next() {
+            Some(seconds) => number(seconds).filter(|s| *s < 61)?,
+            None => 0,
+        };
+        let fraction = match fraction {
+            "" => 0,
+            fraction if fraction.bytes().all(|b| b.is_ascii_digit()) => {
+                number(&format!("{:0<3}", &fraction[..fraction.len().min(3)]))?
+            }
+            _ => return None,
+        };
+        milliseconds += (i64::from(hours) * 3_600 + i64::from(minutes) * 60 + i64::from(seconds))
+            * 1_000
+            + i64::from(fraction);
+
+        if let Some(rest) = offset.strip_prefix(['+', '-']) {
+            let (hours, minutes) = rest
+                .split_once(':')
+                .unwrap_or(rest.split_at(rest.len().min(2)));
+            let minutes = if minutes.is_empty() {
+                0
+            } else {
+                number(minutes)?
+            };
+            let shift = (i64::from(number(hours)?) * 60 + i64::from(minutes)) * 60_000;
+            milliseconds += if offset.starts_with('+') {
+                -shift
+            } else {
+                shift
+            };
+        } else if !offset.is_empty() && offset != "Z" {
+            return None;
+        }
+    }
+
+    u64::try_from(milliseconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_iso_date_time() {
+        assert_eq!(parse_iso_date_time("1970-01-01"), Some(0));
+        assert_eq!(parse_iso_date_time("2021-09-30"), Some(1632960000000));
+        assert_eq!(
+            parse_iso_date_time("2021-09-30T21:21:00.123Z"),
+            Some(1633036860123)
+        );
+        assert_eq!(
+            parse_iso_date_time("2021-10-01T00:21:00+03:00"),
+            Some(1633036860000)
+        );
+        assert_eq!(parse_iso_date_time("2021-13-01"), None);
+        assert_eq!(parse_iso_date_time("1969-12-31"), None);
+        assert_eq!(parse_iso_date_time("2021-10-01T10"), None);
+    }
+
+    #[test]
+    fn it_converts_days() {
+        for (days, date) in [
+            (0, (1970, 1, 1)),
+            (18_901, (2021, 10, 1)),
+            (11_016, (2000, 2, 29)),
+        ] {
+            assert_eq!(civil_from_days(days), date);
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days);
+        }
+    }
+
+    #[test]
+    fn it_formats_iso_dates() {
+        assert_eq!(format_iso_date(0), "1970-01-01");
+        assert_eq!(format_iso_date(1633036860000), "2021-09-30");
+        assert_eq!(format_iso_date(951_782_400_000), "2000-02-29");
+        assert_eq!(format_iso_date(4_102_444_799_999), "2099-12-31");
+    }
+}
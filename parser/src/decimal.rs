@@ -0,0 +1,61 @@
+//! The `decimal` module converts between decimal amounts, e.g. `100.25`, and hundredths or
+//! other minor units.
+
+/// Parses a decimal amount with at most two fraction digits into hundredths.
+pub(crate) fn parse_hundredths(s: &str, separator: char) -> Option<u64> {
+    parse_minor_units(s, separator, 2)
+}
+
+/// Parses a decimal amount with at most `digits` fraction digits into minor units, e.g.
+/// thousandths for three digits.
+pub(crate) fn parse_minor_units(s: &str, separator: char, digits: u32) -> Option<u64> {
+    let (units, fraction) = s.split_once(separator).unwrap_or((s, ""));
+    if units.is_empty()
+        || fraction.len() > digits as usize
+        || (digits == 0 && s.contains(separator))
+        || !units
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let fraction = match digits {
+        0 => 0,
+        _ => format!("{:0<width$}", fraction, width = digits as usize)
+            .parse::<u64>()
+            .ok()?,
+    };
+    units
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(10u64.checked_pow(digits)?)?
+        .checked_add(fraction)
+}
+
+/// Formats hundredths as a decimal amount with two fraction digits.
+pub(crate) fn format_hundredths(amount: u128, separator: char) -> String {
+    format!("{}{}{:02}", amount / 100, separator, amount % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_hundredths() {
+        assert_eq!(parse_hundredths("100.25", '.'), Some(10025));
+        assert_eq!(parse_hundredths("100,", ','), Some(10000));
+        assert_eq!(parse_hundredths("7.5", '.'), Some(750));
+        assert_eq!(parse_hundredths("1.001", '.'), None);
+        assert_eq!(parse_hundredths(".5", '.'), None);
+        assert_eq!(parse_hundredths("-1.00", '.'), None);
+        assert_eq!(parse_hundredths("184467440737095516.16", '.'), None);
+        assert_eq!(parse_minor_units("1000", '.', 0), Some(1000));
+        assert_eq!(parse_minor_units("1000.", '.', 0), None);
+        assert_eq!(parse_minor_units("1.234", '.', 3), Some(1234));
+        assert_eq!(parse_minor_units("1.2", '.', 3), Some(1200));
+        assert_eq!(format_hundredths(10025, ','), "100,25");
+        assert_eq!(format_hundredths(5, '.'), "0.05");
+    }
+}
@@ -3,10 +3,36 @@
 //! Variants:
 //! - `UnknownTransactionStatus`: An unknown transaction status was encountered.
 //! - `UnknownTransactionType`: An unknown transaction type was encountered.
+//! - `UnknownField`: An unknown transaction field was encountered.
+//! - `InvalidValue`: An invalid value of a transaction field was encountered.
+//! - `UnknownFormat`: An unknown file format was encountered.
 //! - `IoError`: An I/O error occurred.
 //! - `InvalidCsvHeader`: An invalid CSV header was encountered.
 //! - `InvalidCsvFormat`: An invalid CSV format was encountered.
+//! - `InvalidCsvColumns`: An invalid CSV column mapping was encountered.
 //! - `InvalidTxtFormat`: An invalid TXT format was encountered.
+//! - `InvalidBinFormat`: An invalid BIN format was encountered.
+//! - `ChecksumMismatch`: A checksum does not match the data it covers.
+//! - `InvalidBinIndex`: An invalid or stale BIN index was encountered.
+//! - `InvalidXmlFormat`: An invalid XML format was encountered.
+//! - `InvalidCamt053Format`: An invalid camt.053 statement was encountered.
+//! - `InvalidMt940Format`: An invalid MT940 statement was encountered.
+//! - `InvalidOfxFormat`: An invalid OFX format was encountered.
+//! - `InvalidQifFormat`: An invalid QIF format was encountered.
+//! - `InvalidFixedWidthFormat`: An invalid fixed-width format was encountered.
+//! - `InvalidFixedWidthLayout`: An invalid fixed-width layout was encountered.
+//! - `InvalidArrowFormat`: An invalid Arrow format was encountered.
+//! - `InvalidParquetFormat`: An invalid Parquet format was encountered.
+//! - `InvalidMsgpackFormat`: An invalid MessagePack format was encountered.
+//! - `InvalidCborFormat`: An invalid CBOR format was encountered.
+//! - `InvalidProtobufFormat`: An invalid Protocol Buffers format was encountered.
+//! - `InvalidAvroFormat`: An invalid Avro format was encountered.
+//! - `InvalidSqliteFormat`: An invalid SQLite database or query was encountered.
+//! - `InvalidXlsxFormat`: An invalid XLSX format was encountered.
+//! - `InvalidYamlFormat`: An invalid YAML format was encountered.
+//! - `InvalidTomlFormat`: An invalid TOML format was encountered.
+//! - `UnsupportedWrite`: Writing is not supported by a format.
+//! - `InvalidFilter`: An invalid filter expression was encountered.
 
 use std::error::Error;
 use std::fmt::Display;
@@ -18,16 +44,66 @@ pub enum ParserError {
     UnknownTransactionStatus(String),
     /// An unknown transaction type was encountered.
     UnknownTransactionType(String),
+    /// An unknown transaction field was encountered.
+    UnknownField(String),
+    /// An invalid value of a transaction field was encountered.
+    InvalidValue(String),
+    /// An unknown file format was encountered.
+    UnknownFormat(String),
     /// An I/O error occurred.
     IoError(std::io::Error),
     /// An invalid CSV header was encountered.
     InvalidCsvHeader(String),
     /// An invalid CSV format was encountered.
     InvalidCsvFormat(String),
+    /// An invalid CSV column mapping was encountered.
+    InvalidCsvColumns(String),
     /// An invalid TXT format was encountered.
     InvalidTxtFormat(String),
     /// An invalid BIN format was encountered.
     InvalidBinFormat(String),
+    /// A checksum does not match the data it covers.
+    ChecksumMismatch(String),
+    /// An invalid or stale BIN index was encountered.
+    InvalidBinIndex(String),
+    /// An invalid XML format was encountered.
+    InvalidXmlFormat(String),
+    /// An invalid camt.053 statement was encountered.
+    InvalidCamt053Format(String),
+    /// An invalid MT940 statement was encountered.
+    InvalidMt940Format(String),
+    /// An invalid OFX format was encountered.
+    InvalidOfxFormat(String),
+    /// An invalid QIF format was encountered.
+    InvalidQifFormat(String),
+    /// An invalid fixed-width format was encountered.
+    InvalidFixedWidthFormat(String),
+    /// An invalid fixed-width layout was encountered.
+    InvalidFixedWidthLayout(String),
+    /// An invalid Arrow format was encountered.
+    InvalidArrowFormat(String),
+    /// An invalid Parquet format was encountered.
+    InvalidParquetFormat(String),
+    /// An invalid MessagePack format was encountered.
+    InvalidMsgpackFormat(String),
+    /// An invalid CBOR format was encountered.
+    InvalidCborFormat(String),
+    /// An invalid Protocol Buffers format was encountered.
+    InvalidProtobufFormat(String),
+    /// An invalid Avro format was encountered.
+    InvalidAvroFormat(String),
+    /// An invalid SQLite database or query was encountered.
+    InvalidSqliteFormat(String),
+    /// An invalid XLSX format was encountered.
+    InvalidXlsxFormat(String),
+    /// An invalid YAML format was encountered.
+    InvalidYamlFormat(String),
+    /// An invalid TOML format was encountered.
+    InvalidTomlFormat(String),
+    /// Writing is not supported by a format.
+    UnsupportedWrite(String),
+    /// An invalid filter expression was encountered.
+    InvalidFilter(String),
 }
 
 impl Display for ParserError {
@@ -39,6 +115,15 @@ impl Display for ParserError {
             ParserError::UnknownTransactionType(type_) => {
                 write!(f, "Unknown transaction type: {}", type_)
             }
+            ParserError::UnknownField(field) => {
+                write!(f, "Unknown field: {}", field)
+            }
+            ParserError::InvalidValue(value) => {
+                write!(f, "Invalid value: {}", value)
+            }
+            ParserError::UnknownFormat(format) => {
+                write!(f, "Unknown format: {}", format)
+            }
             ParserError::IoError(e) => {
                 write!(f, "IO error: {}", e)
             }
@@ -48,12 +133,78 @@ impl Display for ParserError {
             ParserError::InvalidCsvFormat(line) => {
                 write!(f, "Invalid CSV format: {}", line)
             }
+            ParserError::InvalidCsvColumns(columns) => {
+                write!(f, "Invalid CSV columns: {}", columns)
+            }
             ParserError::InvalidTxtFormat(line) => {
                 write!(f, "Invalid TXT format: {}", line)
             }
             ParserError::InvalidBinFormat(line) => {
                 write!(f, "Invalid BIN format: {}", line)
             }
+            ParserError::ChecksumMismatch(message) => {
+                write!(f, "Checksum mismatch: {}", message)
+            }
+            ParserError::InvalidBinIndex(message) => {
+                write!(f, "Invalid BIN index: {}", message)
+            }
+            ParserError::InvalidXmlFormat(message) => {
+                write!(f, "Invalid XML format: {}", message)
+            }
+            ParserError::InvalidCamt053Format(message) => {
+                write!(f, "Invalid camt.053 format: {}", message)
+            }
+            ParserError::InvalidMt940Format(line) => {
+                write!(f, "Invalid MT940 format: {}", line)
+            }
+            ParserError::InvalidOfxFormat(message) => {
+                write!(f, "Invalid OFX format: {}", message)
+            }
+            ParserError::InvalidQifFormat(message) => {
+                write!(f, "Invalid QIF format: {}", message)
+            }
+            ParserError::InvalidFixedWidthFormat(message) => {
+                write!(f, "Invalid fixed-width format: {}", message)
+            }
+            ParserError::InvalidFixedWidthLayout(message) => {
+                write!(f, "Invalid fixed-width layout: {}", message)
+            }
+            ParserError::InvalidArrowFormat(message) => {
+                write!(f, "Invalid Arrow format: {}", message)
+            }
+            ParserError::InvalidParquetFormat(message) => {
+                write!(f, "Invalid Parquet format: {}", message)
+            }
+            ParserError::InvalidMsgpackFormat(message) => {
+                write!(f, "Invalid MessagePack format: {}", message)
+            }
+            ParserError::InvalidCborFormat(message) => {
+                write!(f, "Invalid CBOR format: {}", message)
+            }
+            ParserError::InvalidProtobufFormat(message) => {
+                write!(f, "Invalid Protocol Buffers format: {}", message)
+            }
+            ParserError::InvalidAvroFormat(message) => {
+                write!(f, "Invalid Avro format: {}", message)
+            }
+            ParserError::InvalidSqliteFormat(message) => {
+                write!(f, "Invalid SQLite format: {}", message)
+            }
+            ParserError::InvalidXlsxFormat(message) => {
+                write!(f, "Invalid XLSX format: {}", message)
+            }
+            ParserError::InvalidYamlFormat(message) => {
+                write!(f, "Invalid YAML format: {}", message)
+            }
+            ParserError::InvalidTomlFormat(message) => {
+                write!(f, "Invalid TOML format: {}", message)
+            }
+            ParserError::UnsupportedWrite(format) => {
+                write!(f, "Writing is not supported by the {} format", format)
+            }
+            ParserError::InvalidFilter(message) => {
+                write!(f, "Invalid filter: {}", message)
+            }
         }
     }
 }
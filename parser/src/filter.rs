@@ -0,0 +1,427 @@
+//! The `filter` module provides a small expression language for selecting transactions.
+//!
+//! An expression compares `Transaction` fields with literal values and combines the
+//! comparisons with logical operators, for example:
+//!
+//! ```text
+//! tx_type == TRANSFER && amount > 1000
+//! (status == FAILURE || status == PENDING) && !(from_user_id == 0)
+//! timestamp >= 1633036860000 && timestamp < 1633040000000
+//! ```
+//!
+//! Supported fields are `tx_id`, `tx_type`, `from_user_id`, `to_user_id`, `amount`,
+//! `timestamp`, `status` and `description` (case-insensitive). Numeric fields support
+//! `==`, `!=`, `<`, `<=`, `>` and `>=`, the other fields support `==` and `!=` only.
+//! String values may be quoted with double quotes.
+
+use crate::error::ParserError;
+use crate::transaction::{Field, Transaction, TransactionStatus, TransactionType};
+use std::str::FromStr;
+
+/// The `Filter` struct is a compiled filter expression.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    pub fn parse(s: &str) -> Result<Self, ParserError> {
+        let tokens = tokenize(s)?;
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(ParserError::InvalidFilter(format!(
+                "Unexpected token: {}",
+                token
+            )));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Returns `true` if the transaction satisfies the filter.
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.expr.eval(transaction)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Number(u64),
+    Type(TransactionType),
+    Status(TransactionStatus),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Field, Op, Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, t: &Transaction) -> bool {
+        match self {
+            Expr::Compare(field, op, value) => match (field, value) {
+                (Field::TxId, Value::Number(n)) => op.compare(&t.tx_id, n),
+                (Field::FromUserId, Value::Number(n)) => op.compare(&t.from_user_id, n),
+                (Field::ToUserId, Value::Number(n)) => op.compare(&t.to_user_id, n),
+                (Field::Amount, Value::Number(n)) => op.compare(&t.amount, n),
+                (Field::Timestamp, Value::Number(n)) => op.compare(&t.timestamp, n),
+                (Field::TxType, Value::Type(v)) => (*op == Op::Eq) == (t.tx_type == *v),
+                (Field::Status, Value::Status(v)) => (*op == Op::Eq) == (t.status == *v),
+                (Field::Description, Value::Text(s)) => (*op == Op::Eq) == (t.description == *s),
+                _ => false,
+            },
+            Expr::Not(e) => !e.eval(t),
+            Expr::And(l, r) => l.eval(t) && r.eval(t),
+            Expr::Or(l, r) => l.eval(t) || r.eval(t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "{}", s),
+            Token::Text(s) => write!(f, "\"{}\"", s),
+            Token::Op(op) => write!(f, "{:?}", op),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParserError> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(ParserError::InvalidFilter(format!("Expected {}{}", c, c)));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.next_if_eq(&'=').is_some();
+                let token = match (c, followed_by_eq) {
+                    ('=', true) => Token::Op(Op::Eq),
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    ('>', false) => Token::Op(Op::Gt),
+                    _ => return Err(ParserError::InvalidFilter("Expected ==".to_string())),
+                };
+                tokens.push(token);
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => text.push(escaped),
+                            None => {
+                                return Err(ParserError::InvalidFilter(
+                                    "Unterminated string".to_string(),
+                                ));
+                            }
+                        },
+                        Some(c) => text.push(c),
+                        None => {
+                            return Err(ParserError::InvalidFilter(
+                                "Unterminated string".to_string(),
+                            ));
+                        }
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => {
+                return Err(ParserError::InvalidFilter(format!(
+                    "Unexpected character: {}",
+                    c
+                )));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, ParserError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(ParserError::InvalidFilter(
+                "Unexpected end of expression".to_string(),
+            ))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParserError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParserError> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParserError> {
+        match self.next()? {
+            Token::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                match self.next()? {
+                    Token::RParen => Ok(expr),
+                    token => Err(ParserError::InvalidFilter(format!(
+                        "Expected ), found {}",
+                        token
+                    ))),
+                }
+            }
+            Token::Ident(name) => self.parse_comparison(
+                name.parse()
+                    .map_err(|e: ParserError| ParserError::InvalidFilter(e.to_string()))?,
+            ),
+            token => Err(ParserError::InvalidFilter(format!(
+                "Expected field name, found {}",
+                token
+            ))),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Expr, ParserError> {
+        let op = match self.next()? {
+            Token::Op(op) => op,
+            token => {
+                return Err(ParserError::InvalidFilter(format!(
+                    "Expected comparison operator, found {}",
+                    token
+                )));
+            }
+        };
+
+        let raw = match self.next()? {
+            Token::Ident(s) | Token::Text(s) => s,
+            token => {
+                return Err(ParserError::InvalidFilter(format!(
+                    "Expected value, found {}",
+                    token
+                )));
+            }
+        };
+
+        let value = match field {
+            Field::TxId
+            | Field::FromUserId
+            | Field::ToUserId
+            | Field::Amount
+            | Field::Timestamp => Value::Number(raw.parse::<u64>().map_err(|_| {
+                ParserError::InvalidFilter(format!("Expected number, found {}", raw))
+            })?),
+            Field::TxType => Value::Type(
+                raw.to_ascii_uppercase()
+                    .parse::<TransactionType>()
+                    .map_err(|e| ParserError::InvalidFilter(e.to_string()))?,
+            ),
+            Field::Status => Value::Status(
+                raw.to_ascii_uppercase()
+                    .parse::<TransactionStatus>()
+                    .map_err(|e| ParserError::InvalidFilter(e.to_string()))?,
+            ),
+            Field::Description => Value::Text(raw),
+        };
+
+        if !matches!(value, Value::Number(_)) && !matches!(op, Op::Eq | Op::Ne) {
+            return Err(ParserError::InvalidFilter(format!(
+                "Field {} supports only == and !=",
+                field
+            )));
+        }
+
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(
+        tx_type: TransactionType,
+        amount: u64,
+        status: TransactionStatus,
+    ) -> Transaction {
+        Transaction {
+            tx_type,
+            amount,
+            status,
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn it_matches_comparisons() {
+        let filter = Filter::parse("tx_type == TRANSFER && amount > 1000").unwrap();
+        assert!(filter.matches(&transaction(
+            TransactionType::Transfer,
+            1001,
+            TransactionStatus::Success
+        )));
+        assert!(!filter.matches(&transaction(
+            TransactionType::Transfer,
+            1000,
+            TransactionStatus::Success
+        )));
+        assert!(!filter.matches(&transaction(
+            TransactionType::Deposit,
+            5000,
+            TransactionStatus::Success
+        )));
+    }
+
+    #[test]
+    fn it_respects_precedence_and_negation() {
+        let filter =
+            Filter::parse("!(status == SUCCESS) && (amount <= 10 || tx_type != deposit)").unwrap();
+        assert!(filter.matches(&transaction(
+            TransactionType::Deposit,
+            10,
+            TransactionStatus::Failure
+        )));
+        assert!(filter.matches(&transaction(
+            TransactionType::Withdrawal,
+            500,
+            TransactionStatus::Pending
+        )));
+        assert!(!filter.matches(&transaction(
+            TransactionType::Deposit,
+            500,
+            TransactionStatus::Failure
+        )));
+        assert!(!filter.matches(&transaction(
+            TransactionType::Withdrawal,
+            5,
+            TransactionStatus::Success
+        )));
+    }
+
+    #[test]
+    fn it_is_invalid_filter() {
+        for expr in [
+            "",
+            "amount >",
+            "amount > abc",
+            "unknown == 1",
+            "status < SUCCESS",
+            "tx_type == LOAN",
+            "(amount > 1",
+            "amount > 1 amount",
+            "amount = 1",
+            "description == \"abc\\",
+        ] {
+            assert!(
+                Filter::parse(expr).is_err_and(|e| matches!(e, ParserError::InvalidFilter(_))),
+                "{}",
+                expr
+            );
+        }
+    }
+}
@@ -0,0 +1,602 @@
+//! The `fixed_width_parser` module provides functionality to parse financial transactions from
+//! fixed-width files, such as COBOL-style records.
+//!
+//! Every line is one record, and its fields are found at fixed offsets, as described by a
+//! `FixedWidthLayout`. A layout has one field per line, as
+//! `<name> <offset> <length> [<option>=<value>...]`, where the offset is counted in characters
+//! from 0 and lines starting with `#` are comments. The options are:
+//! - `field`: the transaction field stored in the layout field; fields without it are fillers,
+//!   written as padding and ignored when reading.
+//! - `padding`: `space`, `zero` or any other character; numbers are padded with zeros on the
+//!   left and text with spaces on the right by default.
+//! - `format`: how numbers are written: `display` (digits only, the default), `signed` (with a
+//!   leading `+` or `-`) or `zoned` (with the sign overpunched on the last digit).
+//! - `decimals`: the number of implied decimals of the `AMOUNT`, which is kept in hundredths.
+//!
+//! Writing fails for values that do not fit into their field, numbers and text alike.
+//!
+//! For example:
+//! ```text
+//! # name    offset length options
+//! TXN-ID    0      16     field=tx_id
+//! TXN-AMT   16     11     field=amount format=zoned decimals=2
+//! FILLER    27     3
+//! TXN-DESC  30     40     field=description
+//! ```
+
+use crate::error::ParserError;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
+
+/// How the value of a numeric field is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumericFormat {
+    /// Unsigned digits.
+    Display,
+    /// Digits with a leading `+` or `-` sign.
+    Signed,
+    /// Digits with the sign overpunched on the last digit, e.g. `{` for +0 and `J` for -1.
+    Zoned,
+}
+
+impl FromStr for NumericFormat {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "display" => Ok(NumericFormat::Display),
+            "signed" => Ok(NumericFormat::Signed),
+            "zoned" => Ok(NumericFormat::Zoned),
+            _ => Err(ParserError::InvalidFixedWidthLayout(format!(
+                "Unknown numeric format: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// A field of a fixed-width record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LayoutField {
+    name: String,
+    field: Option<Field>,
+    offset: usize,
+    length: usize,
+    padding: char,
+    format: NumericFormat,
+    decimals: u32,
+}
+
+impl LayoutField {
+    fn is_numeric(&self) -> bool {
+        self.field.is_some_and(|field| field.is_numeric())
+    }
+
+    /// Returns the factor between the value of the field and the amount in hundredths.
+    fn scale(&self) -> u64 {
+        10u64.pow(self.decimals.abs_diff(2))
+    }
+
+    fn parse(&self, line: &[char], transaction: &mut Transaction) -> Result<(), ParserError> {
+        let Some(field) = self.field else {
+            return Ok(());
+        };
+        let raw = line[self.offset..self.offset + self.length]
+            .iter()
+            .collect::<String>();
+
+        if !self.is_numeric() {
+            let value = raw.trim_end_matches(self.padding);
+            return field
+                .set_value(transaction, value)
+                .map_err(|_| self.invalid(&raw));
+        }
+
+        let mut value = self.parse_number(&raw)?;
+        if field == Field::Amount {
+            value = if self.decimals <= 2 {
+                value.checked_mul(self.scale())
+            } else {
+                (value % self.scale() == 0).then(|| value / self.scale())
+            }
+            .ok_or_else(|| self.invalid(&raw))?;
+        }
+        field
+            .set_value(transaction, &value.to_string())
+            .map_err(|_| self.invalid(&raw))
+    }
+
+    fn parse_number(&self, raw: &str) -> Result<u64, ParserError> {
+        let trimmed = raw.trim();
+        let (negative, digits) = match self.format {
+            NumericFormat::Display => (false, trimmed.to_string()),
+            NumericFormat::Signed => match trimmed.strip_prefix('-') {
+                Some(digits) => (true, digits.to_string()),
+                None => (
+                    false,
+                    trimmed.strip_prefix('+').unwrap_or(trimmed).to_string(),
+                ),
+            },
+            NumericFormat::Zoned => {
+                let mut digits = trimmed.to_string();
+                let last = digits.pop().ok_or_else(|| self.invalid(raw))?;
+                let (negative, digit) = match last {
+                    '{' => (false, '0'),
+                    'A'..='I' => (false, (b'1' + (last as u8 - b'A')) as char),
+                    '}' => (true, '0'),
+                    'J'..='R' => (true, (b'1' + (last as u8 - b'J')) as char),
+                    digit => (false, digit),
+                };
+                digits.push(digit);
+                (negative, digits)
+            }
+        };
+
+        let digits = digits.trim_start_matches(self.padding);
+        let digits = if digits.is_empty() && !trimmed.is_empty() {
+            "0"
+        } else {
+            digits
+        };
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.invalid(raw));
+        }
+        let value = digits.parse::<u64>().map_err(|_| self.invalid(raw))?;
+        if negative && value != 0 {
+            return Err(ParserError::InvalidFixedWidthFormat(format!(
+                "{}: Negative value {}",
+                self.name, raw
+            )));
+        }
+        Ok(value)
+    }
+
+    fn write(&self, line: &mut [char], transaction: &Transaction) -> Result<(), ParserError> {
+        let target = &mut line[self.offset..self.offset + self.length];
+        let Some(field) = self.field else {
+            target.fill(self.padding);
+            return Ok(());
+        };
+
+        if !self.is_numeric() {
+            let value = field.value(transaction);
+            let chars = value.chars().collect::<Vec<_>>();
+            if chars.len() > self.length {
+                return Err(ParserError::InvalidFixedWidthFormat(format!(
+                    "{}: Text {} does not fit in {} characters",
+                    self.name, value, self.length
+                )));
+            }
+            target[..chars.len()].copy_from_slice(&chars);
+            target[chars.len()..].fill(self.padding);
+            return Ok(());
+        }
+
+        let mut value = field.number(transaction).unwrap_or_default();
+        if field == Field::Amount {
+            value = if self.decimals >= 2 {
+                value.checked_mul(self.scale())
+            } else {
+                (value % self.scale() == 0).then(|| value / self.scale())
+            }
+            .ok_or_else(|| {
+                ParserError::InvalidFixedWidthFormat(format!(
+                    "{}: Amount {} cannot be written with {} decimals",
+                    self.name, value, self.decimals
+                ))
+            })?;
+        }
+
+        let mut digits = value.to_string();
+        let sign = match self.format {
+            NumericFormat::Display => "",
+            NumericFormat::Signed => "+",
+            NumericFormat::Zoned => {
+                let last = digits.pop().unwrap_or('0');
+                digits.push(match last {
+                    '0' => '{',
+                    digit => (b'A' + (digit as u8 - b'1')) as char,
+                });
+                ""
+            }
+        };
+        let width = self.length.saturating_sub(sign.len());
+        if digits.len() > width {
+            return Err(ParserError::InvalidFixedWidthFormat(format!(
+                "{}: Value {} does not fit in {} characters",
+                self.name, value, self.length
+            )));
+        }
+        // The sign stays in front of zeros, but is padded like the digits otherwise.
+        let padded = if self.padding == '0' {
+            format!("{}{}{}", sign, "0".repeat(width - digits.len()), digits)
+        } else {
+            let padding = self.padding.to_string().repeat(width - digits.len());
+            format!("{}{}{}", padding, sign, digits)
+        };
+        for (target, c) in target.iter_mut().zip(padded.chars()) {
+            *target = c;
+        }
+        Ok(())
+    }
+
+    fn invalid(&self, raw: &str) -> ParserError {
+        ParserError::InvalidFixedWidthFormat(format!("{}: {}", self.name, raw))
+    }
+}
+
+/// The `FixedWidthLayout` struct describes the fields of fixed-width records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidthLayout {
+    fields: Vec<LayoutField>,
+}
+
+impl Default for FixedWidthLayout {
+    /// Returns a layout with all transaction fields, in the order of the standard header.
+    fn default() -> Self {
+        let mut offset = 0;
+        let fields = Field::ALL
+            .iter()
+            .map(|field| {
+                let (length, padding) = match field {
+                    Field::TxType => (10, ' '),
+                    Field::Status => (7, ' '),
+                    Field::Description => (100, ' '),
+                    _ => (20, '0'),
+                };
+                offset += length;
+                LayoutField {
+                    name: field.to_string(),
+                    field: Some(*field),
+                    offset: offset - length,
+                    length,
+                    padding,
+                    format: NumericFormat::Display,
+                    decimals: 2,
+                }
+            })
+            .collect();
+        Self { fields }
+    }
+}
+
+impl FromStr for FixedWidthLayout {
+    type Err = ParserError;
+
+    /// Parses a layout with one field per line, as `<name> <offset> <length> [<option>=<value>...]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| ParserError::InvalidFixedWidthLayout(message);
+        let mut fields: Vec<LayoutField> = vec![];
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let name = parts.next().unwrap_or_default().to_string();
+            let mut number = |what: &str| {
+                parts
+                    .next()
+                    .and_then(|part| part.parse::<usize>().ok())
+                    .ok_or_else(|| invalid(format!("Missing or invalid {} of {}", what, name)))
+            };
+            let offset = number("offset")?;
+            let length = number("length")?;
+            if length == 0 {
+                return Err(invalid(format!("Empty field: {}", name)));
+            }
+
+            let mut layout_field = LayoutField {
+                name: name.clone(),
+                field: None,
+                offset,
+                length,
+                padding: ' ',
+                format: NumericFormat::Display,
+                decimals: 2,
+            };
+            let (mut padding, mut format, mut decimals) = (None, None, None);
+            for option in parts {
+                let (key, value) = option
+                    .split_once('=')
+                    .ok_or_else(|| invalid(format!("Invalid option of {}: {}", name, option)))?;
+                match key.to_ascii_lowercase().as_str() {
+                    "field" => layout_field.field = Some(value.parse::<Field>()?),
+                    "padding" => {
+                        padding = Some(match value.to_ascii_lowercase().as_str() {
+                            "space" => ' ',
+                            "zero" => '0',
+                            _ if value.chars().count() == 1 => value.chars().next().unwrap_or(' '),
+                            _ => return Err(invalid(format!("Invalid padding of {}", name))),
+                        })
+                    }
+                    "format" => format = Some(value.parse::<NumericFormat>()?),
+                    "decimals" => {
+                        decimals = Some(value.parse::<u32>().ok().filter(|d| *d <= 18).ok_or_else(
+                            || invalid(format!("Invalid decimals of {}: {}", name, value)),
+                        )?)
+                    }
+                    _ => return Err(invalid(format!("Unknown option of {}: {}", name, key))),
+                }
+            }
+
+            if !layout_field.is_numeric() && format.is_some() {
+                return Err(invalid(format!("Numeric format of a text field: {}", name)));
+            }
+            if layout_field.field != Some(Field::Amount) && decimals.is_some() {
+                return Err(invalid(format!(
+                    "Decimals of a field other than AMOUNT: {}",
+                    name
+                )));
+            }
+            if format == Some(NumericFormat::Signed) && length < 2 {
+                return Err(invalid(format!("Signed field is too short: {}", name)));
+            }
+            let numeric = layout_field.is_numeric();
+            layout_field.padding = padding.unwrap_or(if numeric { '0' } else { ' ' });
+            layout_field.format = format.unwrap_or(NumericFormat::Display);
+            layout_field.decimals = decimals.unwrap_or(2);
+
+            if let Some(other) = fields.iter().find(|other| {
+                other.offset < offset + length && offset < other.offset + other.length
+            }) {
+                return Err(invalid(format!("{} overlaps {}", name, other.name)));
+            }
+            if let Some(field) = layout_field.field
+                && fields.iter().any(|other| other.field == Some(field))
+            {
+                return Err(invalid(format!("Duplicate field: {}", field)));
+            }
+            fields.push(layout_field);
+        }
+
+        if fields.iter().all(|field| field.field.is_none()) {
+            return Err(invalid("No transaction fields".to_string()));
+        }
+        fields.sort_by_key(|field| field.offset);
+
+        Ok(Self { fields })
+    }
+}
+
+impl FixedWidthLayout {
+    /// Returns the length of a record, up to the end of the last field.
+    pub fn record_length(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| field.offset + field.length)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn parse_record(&self, line: &str) -> Result<Transaction, ParserError> {
+        let mut chars = line.chars().collect::<Vec<_>>();
+        let record_length = self.record_length();
+        if chars.len() > record_length {
+            return Err(ParserError::InvalidFixedWidthFormat(format!(
+                "Record is longer than {} characters: {}",
+                record_length, line
+            )));
+        }
+        // Trailing spaces are often trimmed from records.
+        chars.resize(record_length, ' ');
+
+        let mut transaction = Transaction::default();
+        for field in &self.fields {
+            field.parse(&chars, &mut transaction)?;
+        }
+        Ok(transaction)
+    }
+
+    fn serialize_record(&self, transaction: &Transaction) -> Result<String, ParserError> {
+        let mut line = vec![' '; self.record_length()];
+        for field in &self.fields {
+            field.write(&mut line, transaction)?;
+        }
+        Ok(line.into_iter().collect())
+    }
+}
+
+/// The `FixedWidthParserFactory` struct is a factory for creating fixed-width parsers.
+pub struct FixedWidthParserFactory;
+
+impl ParserFactory for FixedWidthParserFactory {
+    type Parser = FixedWidthParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        FixedWidthParser::default()
+    }
+}
+
+/// The `FixedWidthParser` struct is a parser for fixed-width files.
+#[derive(Default)]
+pub struct FixedWidthParser {
+    layout: FixedWidthLayout,
+}
+
+impl Parser for FixedWidthParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let layout = self.layout.clone();
+        Box::new(
+            BufReader::new(r)
+                .lines()
+                .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+                .map(move |line| {
+                    let line = line?;
+                    layout.parse_record(line.trim_end_matches('\r'))
+                }),
+        )
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl FixedWidthParser {
+    /// Creates a parser for fixed-width files with the given layout.
+    pub fn new(layout: FixedWidthLayout) -> Self {
+        Self { layout }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        for transaction in transactions {
+            let line = self.layout.serialize_record(transaction?.borrow())?;
+            buf_writer.write_all(line.as_bytes())?;
+            buf_writer.write_all(b"\n")?;
+        }
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvParserFactory, TransactionStatus, TransactionType};
+    use std::fs::File;
+
+    const LAYOUT: &str = "# name offset length options
+        TXN-ID    0  6 field=tx_id padding=space
+        TXN-CODE  6  1
+        TXN-AMT   7  8 field=amount format=zoned decimals=3
+        TXN-TIME 15 14 field=timestamp format=signed
+        FILLER   29  2 padding=*
+        TXN-DESC 31 10 field=description";
+
+    #[test]
+    fn it_reads_fixed_width() {
+        let parser = FixedWidthParser::new(LAYOUT.parse().unwrap());
+        let records = "    42X0001251{+0001633036860**Payment\n\n000007 0000000}-0000000000000\r\n";
+        let transactions = parser.read_from(&mut records.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].tx_id, 42);
+        assert_eq!(transactions[0].amount, 1251);
+        assert_eq!(transactions[0].timestamp, 1633036860);
+        assert_eq!(transactions[0].description, "Payment");
+        assert_eq!(transactions[1].tx_id, 7);
+        assert_eq!(transactions[1].amount, 0);
+        assert_eq!(transactions[1].timestamp, 0);
+        assert_eq!(transactions[1].description, "");
+    }
+
+    #[test]
+    fn it_reads_written_fixed_width() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        for parser in [
+            FixedWidthParserFactory.create_parser(),
+            FixedWidthParser::new(
+                "id 0 20 field=tx_id\ntype 20 10 field=tx_type\nfrom 30 20 field=from_user_id\n\
+                 to 50 20 field=to_user_id padding=space\namount 70 12 field=amount format=zoned\n\
+                 time 82 15 field=timestamp format=signed\nstatus 97 7 field=status\n\
+                 description 104 30 field=description"
+                    .parse()
+                    .unwrap(),
+            ),
+        ] {
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &transactions).unwrap();
+            let read = parser.read_from(&mut buffer.as_slice()).unwrap();
+            assert_eq!(read, transactions);
+        }
+
+        let mut transaction = Transaction::new(
+            1,
+            TransactionType::Deposit,
+            0,
+            0,
+            12345,
+            0,
+            TransactionStatus::Success,
+            "Long description".to_string(),
+        );
+        let parser = FixedWidthParser::new(LAYOUT.parse().unwrap());
+        assert!(
+            parser
+                .write_to(&mut vec![], std::slice::from_ref(&transaction))
+                .is_err_and(|e| matches!(e, ParserError::InvalidFixedWidthFormat(_)))
+        );
+        transaction.description = "Long descr".to_string();
+        let mut buffer = vec![];
+        parser.write_to(&mut buffer, &[transaction]).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "     1 0012345{+0000000000000**Long descr\n"
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_fixed_width_layout() {
+        for layout in [
+            "",
+            "FILLER 0 10",
+            "ID 0",
+            "ID 0 0 field=tx_id",
+            "ID 0 10 field=unknown",
+            "ID 0 10 field=tx_id\nAMOUNT 5 10 field=amount",
+            "ID 0 10 field=tx_id\nOTHER 10 10 field=tx_id",
+            "DESC 0 10 field=description format=zoned",
+            "ID 0 10 field=tx_id decimals=2",
+            "ID 0 10 field=tx_id align=left",
+        ] {
+            assert!(layout.parse::<FixedWidthLayout>().is_err(), "{}", layout);
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_fixed_width_format() {
+        let parser = FixedWidthParser::new(LAYOUT.parse().unwrap());
+        for records in [
+            "    42X0001251{+0001633036860**Payment toolong\n",
+            "    x2X0001251{+0001633036860**Payment\n",
+            "    42X0000125B+0001633036860**Payment\n",
+            "    42X0001251J+0001633036860**Payment\n",
+            "    42X0001251{-0001633036860**Payment\n",
+        ] {
+            assert!(
+                parser
+                    .read_from(&mut records.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidFixedWidthFormat(_))),
+                "{}",
+                records
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! The `fixture` module provides the strict form of a transaction read from hand-written YAML and
+//! TOML fixtures.
+//!
+//! Unlike the public `Transaction`, a fixture transaction rejects unknown fields, so that typos in
+//! hand-written files are reported instead of ignored, and its `description` may be omitted when
+//! it is empty.
+
+use crate::transaction::{Transaction, TransactionStatus, TransactionType};
+use serde::Deserialize;
+
+/// The `FixtureTransaction` struct is a transaction as written in a fixture.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FixtureTransaction {
+    tx_id: u64,
+    tx_type: TransactionType,
+    from_user_id: u64,
+    to_user_id: u64,
+    amount: u64,
+    timestamp: u64,
+    status: TransactionStatus,
+    #[serde(default)]
+    description: String,
+}
+
+impl From<FixtureTransaction> for Transaction {
+    fn from(fixture: FixtureTransaction) -> Self {
+        Transaction::new(
+            fixture.tx_id,
+            fixture.tx_type,
+            fixture.from_user_id,
+            fixture.to_user_id,
+            fixture.amount,
+            fixture.timestamp,
+            fixture.status,
+            fixture.description,
+        )
+    }
+}
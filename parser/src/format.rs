@@ -0,0 +1,412 @@
+//! The `format` module provides runtime selection of a transaction file format.
+//!
+//! Formats are named by their usual file extension (`csv`, `txt`, `bin`, `xml`, `mt940`, `ofx`,
+//! `qif`, `fixed`, `msgpack`, `cbor`, `yaml`, `toml`) and can be detected from a file path, also when it is
+//! compressed, e.g. `records.csv.gz`. The `yml` extension is
+//! also detected as the `yaml` format. The `fixed` format uses the default fixed-width layout.
+//! The `camt053` format is read-only and must be selected explicitly, as its files end in `.xml`.
+//! The `arrow` (IPC file) and `arrows` (IPC stream) formats are available with the `arrow`
+//! feature, the `parquet` format with the `parquet` feature, the `protobuf` (length-delimited
+//! stream) format with the `protobuf` feature, the `avro` (object container file) format with
+//! the `avro` feature, the `sqlite` (database) format with the `sqlite` feature, and the `xlsx`
+//! (Excel workbook) format with the `xlsx` feature.
+
+#[cfg(feature = "avro")]
+use crate::AvroParserFactory;
+#[cfg(feature = "parquet")]
+use crate::ParquetParserFactory;
+#[cfg(feature = "protobuf")]
+use crate::ProtobufParserFactory;
+#[cfg(feature = "sqlite")]
+use crate::SqliteParserFactory;
+#[cfg(feature = "xlsx")]
+use crate::XlsxParserFactory;
+use crate::compression::Compression;
+use crate::error::ParserError;
+use crate::transaction::Transaction;
+#[cfg(feature = "arrow")]
+use crate::{ArrowIpcFormat, ArrowParser, ArrowParserFactory};
+use crate::{
+    BinParserFactory, Camt053ParserFactory, CborParserFactory, CsvParserFactory,
+    FixedWidthParserFactory, MsgpackParserFactory, Mt940ParserFactory, OfxParserFactory, Parser,
+    ParserFactory, QifParserFactory, TomlParserFactory, Transactions, TxtParserFactory,
+    XmlParserFactory, YamlParserFactory,
+};
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The `Format` enum represents a supported transaction file format.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Format {
+    /// The CSV format.
+    Csv,
+    /// The TXT format.
+    Txt,
+    /// The BIN format.
+    Bin,
+    /// The XML format.
+    Xml,
+    /// The SWIFT MT940 format.
+    Mt940,
+    /// The OFX format.
+    Ofx,
+    /// The QIF format.
+    Qif,
+    /// The fixed-width format with the default layout.
+    FixedWidth,
+    /// The MessagePack format.
+    Msgpack,
+    /// The CBOR format.
+    Cbor,
+    /// The YAML format.
+    Yaml,
+    /// The TOML format.
+    Toml,
+    /// The Apache Arrow IPC file format.
+    #[cfg(feature = "arrow")]
+    Arrow,
+    /// The Apache Arrow IPC stream format.
+    #[cfg(feature = "arrow")]
+    ArrowStream,
+    /// The Apache Parquet format.
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// The length-delimited Protocol Buffers stream format.
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+    /// The Apache Avro object container file format.
+    #[cfg(feature = "avro")]
+    Avro,
+    /// The SQLite database format, with the default table.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// The Excel XLSX workbook format, with a single sheet.
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+    /// The ISO 20022 camt.053 bank statement format, read-only.
+    Camt053,
+}
+
+impl FromStr for Format {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "txt" => Ok(Format::Txt),
+            "bin" => Ok(Format::Bin),
+            "xml" => Ok(Format::Xml),
+            "mt940" => Ok(Format::Mt940),
+            "ofx" => Ok(Format::Ofx),
+            "qif" => Ok(Format::Qif),
+            "fixed" => Ok(Format::FixedWidth),
+            "msgpack" => Ok(Format::Msgpack),
+            "cbor" => Ok(Format::Cbor),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            #[cfg(feature = "arrow")]
+            "arrow" => Ok(Format::Arrow),
+            #[cfg(feature = "arrow")]
+            "arrows" => Ok(Format::ArrowStream),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(Format::Parquet),
+            #[cfg(feature = "protobuf")]
+            "protobuf" => Ok(Format::Protobuf),
+            #[cfg(feature = "avro")]
+            "avro" => Ok(Format::Avro),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(Format::Sqlite),
+            #[cfg(feature = "xlsx")]
+            "xlsx" => Ok(Format::Xlsx),
+            "camt053" => Ok(Format::Camt053),
+            _ => Err(ParserError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Csv => write!(f, "csv"),
+            Format::Txt => write!(f, "txt"),
+            Format::Bin => write!(f, "bin"),
+            Format::Xml => write!(f, "xml"),
+            Format::Mt940 => write!(f, "mt940"),
+            Format::Ofx => write!(f, "ofx"),
+            Format::Qif => write!(f, "qif"),
+            Format::FixedWidth => write!(f, "fixed"),
+            Format::Msgpack => write!(f, "msgpack"),
+            Format::Cbor => write!(f, "cbor"),
+            Format::Yaml => write!(f, "yaml"),
+            Format::Toml => write!(f, "toml"),
+            #[cfg(feature = "arrow")]
+            Format::Arrow => write!(f, "arrow"),
+            #[cfg(feature = "arrow")]
+            Format::ArrowStream => write!(f, "arrows"),
+            #[cfg(feature = "parquet")]
+            Format::Parquet => write!(f, "parquet"),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => write!(f, "protobuf"),
+            #[cfg(feature = "avro")]
+            Format::Avro => write!(f, "avro"),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => write!(f, "sqlite"),
+            #[cfg(feature = "xlsx")]
+            Format::Xlsx => write!(f, "xlsx"),
+            Format::Camt053 => write!(f, "camt053"),
+        }
+    }
+}
+
+impl Format {
+    /// The names of all formats.
+    pub const NAMES: &'static [&'static str] = &[
+        "csv",
+        "txt",
+        "bin",
+        "xml",
+        "mt940",
+        "ofx",
+        "qif",
+        "fixed",
+        "msgpack",
+        "cbor",
+        "yaml",
+        "toml",
+        #[cfg(feature = "arrow")]
+        "arrow",
+        #[cfg(feature = "arrow")]
+        "arrows",
+        #[cfg(feature = "parquet")]
+        "parquet",
+        #[cfg(feature = "protobuf")]
+        "protobuf",
+        #[cfg(feature = "avro")]
+        "avro",
+        #[cfg(feature = "sqlite")]
+        "sqlite",
+        #[cfg(feature = "xlsx")]
+        "xlsx",
+        "camt053",
+    ];
+
+    /// Detects the format from the extension of a path, ignoring a compression extension.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        Compression::strip_extension(path)
+            .extension()?
+            .to_str()?
+            .parse()
+            .ok()
+    }
+
+    /// Reads transactions from a reader one at a time.
+    pub fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a> {
+        match self {
+            Format::Csv => CsvParserFactory.create_parser().read_iter(r),
+            Format::Txt => TxtParserFactory.create_parser().read_iter(r),
+            Format::Bin => BinParserFactory.create_parser().read_iter(r),
+            Format::Xml => XmlParserFactory.create_parser().read_iter(r),
+            Format::Mt940 => Mt940ParserFactory.create_parser().read_iter(r),
+            Format::Ofx => OfxParserFactory.create_parser().read_iter(r),
+            Format::Qif => QifParserFactory.create_parser().read_iter(r),
+            Format::FixedWidth => FixedWidthParserFactory.create_parser().read_iter(r),
+            Format::Msgpack => MsgpackParserFactory.create_parser().read_iter(r),
+            Format::Cbor => CborParserFactory.create_parser().read_iter(r),
+            Format::Yaml => YamlParserFactory.create_parser().read_iter(r),
+            Format::Toml => TomlParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "arrow")]
+            Format::Arrow => ArrowParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "arrow")]
+            Format::ArrowStream => ArrowParser::new(ArrowIpcFormat::Stream).read_iter(r),
+            #[cfg(feature = "parquet")]
+            Format::Parquet => ParquetParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => ProtobufParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "avro")]
+            Format::Avro => AvroParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => SqliteParserFactory.create_parser().read_iter(r),
+            #[cfg(feature = "xlsx")]
+            Format::Xlsx => XlsxParserFactory.create_parser().read_iter(r),
+            Format::Camt053 => Camt053ParserFactory.create_parser().read_iter(r),
+        }
+    }
+
+    /// Writes transactions to a writer.
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        match self {
+            Format::Csv => CsvParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Txt => TxtParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Bin => BinParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Xml => XmlParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Mt940 => Mt940ParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Ofx => OfxParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Qif => QifParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::FixedWidth => FixedWidthParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Msgpack => MsgpackParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Cbor => CborParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Yaml => YamlParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Toml => TomlParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "arrow")]
+            Format::Arrow => ArrowParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "arrow")]
+            Format::ArrowStream => {
+                ArrowParser::new(ArrowIpcFormat::Stream).write_to(writer, transactions)
+            }
+            #[cfg(feature = "parquet")]
+            Format::Parquet => ParquetParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => ProtobufParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "avro")]
+            Format::Avro => AvroParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => SqliteParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            #[cfg(feature = "xlsx")]
+            Format::Xlsx => XlsxParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+            Format::Camt053 => Camt053ParserFactory
+                .create_parser()
+                .write_to(writer, transactions),
+        }
+    }
+
+    /// Writes transactions to a writer one at a time.
+    pub fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError> {
+        match self {
+            Format::Csv => CsvParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Txt => TxtParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Bin => BinParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Xml => XmlParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Mt940 => Mt940ParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Ofx => OfxParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Qif => QifParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::FixedWidth => FixedWidthParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Msgpack => MsgpackParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Cbor => CborParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Yaml => YamlParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Toml => TomlParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "arrow")]
+            Format::Arrow => ArrowParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "arrow")]
+            Format::ArrowStream => {
+                ArrowParser::new(ArrowIpcFormat::Stream).write_iter(writer, transactions)
+            }
+            #[cfg(feature = "parquet")]
+            Format::Parquet => ParquetParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => ProtobufParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "avro")]
+            Format::Avro => AvroParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => SqliteParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            #[cfg(feature = "xlsx")]
+            Format::Xlsx => XlsxParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+            Format::Camt053 => Camt053ParserFactory
+                .create_parser()
+                .write_iter(writer, transactions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_format_from_path() {
+        assert_eq!(
+            Format::from_path(Path::new("../samples/records_example.CSV")),
+            Some(Format::Csv)
+        );
+        assert_eq!(
+            Format::from_path(Path::new("records.bin")),
+            Some(Format::Bin)
+        );
+        assert_eq!(
+            Format::from_path(Path::new("fixtures.yml")),
+            Some(Format::Yaml)
+        );
+        assert_eq!(Format::from_path(Path::new("records.json")), None);
+        assert_eq!(Format::from_path(Path::new("records")), None);
+    }
+}
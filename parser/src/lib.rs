@@ -1,16 +1,87 @@
-//! The `parser` crate provides functionality to parse financial transactions from CSV and TXT files.
+//! The `parser` crate provides functionality to parse financial transactions from CSV, TXT, BIN, XML, MT940,
+//! OFX, QIF, fixed-width, MessagePack, CBOR, YAML and TOML files and to import them from camt.053 bank statements. Apache Arrow
+//! IPC files and record batches are supported with the `arrow` feature, Apache Parquet files
+//! with the `parquet` feature, length-delimited Protocol Buffers streams with the `protobuf`
+//! feature, Apache Avro object container files with the `avro` feature, SQLite databases with the
+//! `sqlite` feature, and Excel XLSX workbooks with the `xlsx` feature.
 
-use crate::error::ParserError;
+#[cfg(feature = "arrow")]
+mod arrow_parser;
+#[cfg(feature = "avro")]
+mod avro_parser;
+mod bin_index;
+mod bin_parser;
+mod camt053_parser;
+mod cbor_parser;
+mod compression;
 mod csv_parser;
+mod date;
+mod decimal;
 mod error;
+mod filter;
+mod fixed_width_parser;
+mod fixture;
+mod format;
+mod msgpack_parser;
+mod mt940_parser;
+mod ofx_parser;
+#[cfg(feature = "parquet")]
+mod parquet_parser;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+#[cfg(feature = "protobuf")]
+mod protobuf_parser;
+mod qif_parser;
+mod record;
+#[cfg(feature = "arrow")]
+mod record_batch;
+#[cfg(feature = "sqlite")]
+mod sqlite_parser;
+mod toml_parser;
 mod transaction;
 mod txt_parser;
-mod bin_parser;
+#[cfg(feature = "xlsx")]
+mod xlsx_parser;
+mod xml_parser;
+mod yaml_parser;
 
-use crate::transaction::Transaction;
-pub use csv_parser::CsvParserFactory;
+#[cfg(feature = "arrow")]
+pub use arrow_parser::{ArrowIpcFormat, ArrowParser, ArrowParserFactory};
+#[cfg(feature = "avro")]
+pub use avro_parser::{AvroCodec, AvroParser, AvroParserFactory};
+pub use bin_index::{BinIndex, BinReader};
+pub use bin_parser::{BinHeader, BinParser, BinParserFactory, BinVersion};
+pub use camt053_parser::Camt053ParserFactory;
+pub use cbor_parser::CborParserFactory;
+pub use compression::{CompressedWriter, Compression};
+pub use csv_parser::{CsvColumns, CsvParser, CsvParserFactory};
+pub use date::format_iso_date;
+pub use error::ParserError;
+pub use filter::Filter;
+pub use fixed_width_parser::{FixedWidthLayout, FixedWidthParser, FixedWidthParserFactory};
+pub use format::Format;
+pub use msgpack_parser::MsgpackParserFactory;
+pub use mt940_parser::Mt940ParserFactory;
+pub use ofx_parser::OfxParserFactory;
+#[cfg(feature = "parquet")]
+pub use parquet_parser::{ParquetCompression, ParquetParser, ParquetParserFactory};
+#[cfg(feature = "protobuf")]
+pub use protobuf_parser::{ProtobufParser, ProtobufParserFactory};
+pub use qif_parser::QifParserFactory;
+#[cfg(feature = "arrow")]
+pub use record_batch::{
+    RecordBatches, from_record_batch, from_record_batches, to_record_batch, to_record_batches,
+    transaction_schema,
+};
+#[cfg(feature = "sqlite")]
+pub use sqlite_parser::{SqliteParser, SqliteParserFactory};
+pub use toml_parser::{TomlParser, TomlParserFactory};
+pub use transaction::{Field, Transaction, TransactionStatus, TransactionType};
 pub use txt_parser::TxtParserFactory;
-pub use bin_parser::BinParserFactory;
+#[cfg(feature = "xlsx")]
+pub use xlsx_parser::{XlsxParser, XlsxParserFactory, XlsxSheets};
+pub use xml_parser::{XmlParser, XmlParserFactory, XmlStyle};
+pub use yaml_parser::{YamlParser, YamlParserFactory};
 
 /// The `ParserFactory` trait represents a factory for creating parsers.
 pub trait ParserFactory {
@@ -19,6 +90,9 @@ pub trait ParserFactory {
     fn create_parser(&self) -> Self::Parser;
 }
 
+/// A boxed iterator over transactions read one at a time.
+pub type Transactions<'a> = Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a>;
+
 /// The `Parser` trait represents a parser for financial transactions.
 pub trait Parser {
     /// Reads transactions from a reader.
@@ -32,4 +106,32 @@ pub trait Parser {
         writer: &mut W,
         transactions: &[Transaction],
     ) -> Result<(), ParserError>;
+
+    /// Reads transactions from a reader one at a time.
+    ///
+    /// The default implementation reads all transactions with `read_from` up front.
+    fn read_iter<'a, R: std::io::Read + 'a>(&self, mut r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        match self.read_from(&mut r) {
+            Ok(transactions) => Box::new(transactions.into_iter().map(Ok)),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    /// Writes transactions to a writer one at a time.
+    ///
+    /// The default implementation collects all transactions and writes them with `write_to`.
+    fn write_iter<W: std::io::Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        let transactions = transactions.into_iter().collect::<Result<Vec<_>, _>>()?;
+        self.write_to(writer, &transactions)
+    }
 }
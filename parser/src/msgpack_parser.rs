@@ -0,0 +1,193 @@
+//! The `msgpack_parser` module provides functionality to parse financial transactions from
+//! MessagePack files.
+//!
+//! Every transaction is a map from the lower-case field names to their values, e.g.
+//! `{"tx_id": 1000000000000000, "tx_type": "DEPOSIT", ...}`, and the maps follow each other
+//! without a surrounding array, so transactions are read and written one at a time. Numbers are
+//! unsigned integers and the other fields are strings.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+/// The `MsgpackParserFactory` struct is a factory for creating MessagePack parsers.
+pub struct MsgpackParserFactory;
+
+impl ParserFactory for MsgpackParserFactory {
+    type Parser = MsgpackParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        MsgpackParser
+    }
+}
+
+/// The `MsgpackParser` struct is a parser for MessagePack files.
+pub struct MsgpackParser;
+
+impl Parser for MsgpackParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = BufReader::new(r);
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut reader).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl MsgpackParser {
+    fn read_record<R: BufRead>(reader: &mut R) -> Result<Option<Transaction>, ParserError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let len = rmp::decode::read_map_len(reader).map_err(|e| invalid(e.to_string()))?;
+        let mut record = Record::new(invalid);
+        for _ in 0..len {
+            let field = record.field(&Self::read_string(reader)?)?;
+            let value = if field.is_numeric() {
+                rmp::decode::read_int::<u64, _>(reader)
+                    .map_err(|e| invalid(format!("{}: {}", field.key(), e)))?
+                    .to_string()
+            } else {
+                Self::read_string(reader)?
+            };
+            record.set(field, &value)?;
+        }
+        record.finish().map(Some)
+    }
+
+    fn read_string<R: Read>(reader: &mut R) -> Result<String, ParserError> {
+        let len = rmp::decode::read_str_len(reader).map_err(|e| invalid(e.to_string()))?;
+        // The length is not trusted: the buffer grows with the bytes actually read.
+        let mut buffer = vec![];
+        reader.take(u64::from(len)).read_to_end(&mut buffer)?;
+        if (buffer.len() as u64) < u64::from(len) {
+            return Err(invalid(format!(
+                "String of {} bytes truncated to {}",
+                len,
+                buffer.len()
+            )));
+        }
+        String::from_utf8(buffer).map_err(|e| invalid(e.to_string()))
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        for transaction in transactions {
+            let transaction = transaction?;
+            let transaction = transaction.borrow();
+            rmp::encode::write_map_len(&mut buf_writer, Field::ALL.len() as u32)
+                .map_err(std::io::Error::from)?;
+            for field in Field::ALL {
+                rmp::encode::write_str(&mut buf_writer, field.key())
+                    .map_err(std::io::Error::from)?;
+                match field.number(transaction) {
+                    Some(number) => rmp::encode::write_uint(&mut buf_writer, number)
+                        .map(|_| ())
+                        .map_err(std::io::Error::from)?,
+                    None => rmp::encode::write_str(&mut buf_writer, &field.value(transaction))
+                        .map_err(std::io::Error::from)?,
+                }
+            }
+        }
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidMsgpackFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_msgpack() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        MsgpackParser.write_to(&mut buffer, &transactions).unwrap();
+        // A fixmap of 8 entries with the fixstr key "tx_id".
+        assert!(buffer.starts_with(b"\x88\xa5tx_id"));
+
+        let read = MsgpackParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, transactions);
+    }
+
+    #[test]
+    fn it_is_invalid_msgpack_format() {
+        let mut buffer = vec![];
+        MsgpackParser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+        let mut unknown = buffer.clone();
+        unknown[2..7].copy_from_slice(b"TX_ID");
+        let mut negative = buffer.clone();
+        // The tx_id 0 is a positive fixint, -1 is a negative fixint.
+        negative[7] = 0xff;
+
+        for msgpack in [
+            b"\x91\x01".as_slice(),
+            &buffer[..buffer.len() - 1],
+            &unknown,
+            &negative,
+            b"\x81\xa5tx_id\x01",
+            b"\x81\xdb\xff\xff\xff\xfftx_id",
+        ] {
+            assert!(
+                MsgpackParser
+                    .read_from(&mut &msgpack[..])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidMsgpackFormat(_))),
+                "{:?}",
+                msgpack
+            );
+        }
+    }
+}
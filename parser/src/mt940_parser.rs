@@ -0,0 +1,544 @@
+//! The `mt940_parser` module provides functionality to parse financial transactions from SWIFT
+//! MT940 customer statements.
+//!
+//! Every `:61:` statement line becomes a transaction, with the `:86:` narrative that follows it
+//! as the description. Fields are mapped as follows:
+//! - `TX_ID`: the reference for the account owner when it is numeric, otherwise the position
+//!   of the transaction in the file, starting from 1.
+//! - `TX_TYPE`: `TRANSFER` for the `NTRF` transaction type, otherwise `DEPOSIT` for a credit
+//!   (`C` or `RD`) and `WITHDRAWAL` for a debit (`D` or `RC`).
+//! - `FROM_USER_ID`, `TO_USER_ID`: always 0.
+//! - `AMOUNT`: the amount in hundredths (cents).
+//! - `TIMESTAMP`: the entry date, or the value date, at midnight UTC.
+//! - `STATUS`: always `SUCCESS`, statements contain booked transactions only.
+//!
+//! When a statement has an opening balance (`:60F:` or `:60M:`), its closing balance (`:62F:` or
+//! `:62M:`) is checked against the amounts. SWIFT block headers and trailers are skipped.
+//!
+//! Transactions are written as a single statement with an opening balance of zero and a
+//! closing balance computed from the amounts. Transfers are written as debits. Descriptions are
+//! wrapped into at most 6 narrative lines of 65 characters, breaking lines before characters
+//! that could not start a continuation line, such as `-`, `{`, `:` or a space, and amounts have
+//! at most 15 characters; larger descriptions and amounts are not written.
+
+use crate::date::{civil_from_days, days_from_civil};
+use crate::decimal::{format_hundredths, parse_hundredths};
+use crate::error::ParserError;
+use crate::transaction::{Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
+
+/// The currency code written to statements, ISO 4217 for "no currency".
+const CURRENCY: &str = "XXX";
+/// The number of characters of a `:86:` narrative line.
+const NARRATIVE_LINE: usize = 65;
+/// The number of lines of a `:86:` narrative.
+const NARRATIVE_LINES: usize = 6;
+/// The number of characters of an amount, with the decimal comma.
+const AMOUNT_LENGTH: usize = 15;
+
+/// The `Mt940ParserFactory` struct is a factory for creating MT940 parsers.
+pub struct Mt940ParserFactory;
+
+impl ParserFactory for Mt940ParserFactory {
+    type Parser = Mt940Parser;
+
+    fn create_parser(&self) -> Self::Parser {
+        Mt940Parser
+    }
+}
+
+/// The `Mt940Parser` struct is a parser for MT940 statements.
+pub struct Mt940Parser;
+
+impl Parser for Mt940Parser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = Mt940Reader {
+            lines: BufReader::new(r).lines(),
+            lookahead: None,
+            pending: None,
+            described: false,
+            balance: None,
+            position: 0,
+        };
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = reader.read_record().transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl Mt940Parser {
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        let mut transactions = transactions.into_iter().peekable();
+
+        let opening_date = match transactions.peek() {
+            Some(Ok(transaction)) => transaction.borrow().timestamp,
+            _ => 0,
+        };
+        write!(
+            buf_writer,
+            ":20:STATEMENT\r\n:25:NOTPROVIDED\r\n:28C:1\r\n:60F:C{}{}{}\r\n",
+            format_date(opening_date),
+            CURRENCY,
+            format_hundredths(0, ',')
+        )?;
+
+        let mut balance: i128 = 0;
+        let mut closing_date = opening_date;
+        for transaction in transactions {
+            let transaction = transaction?;
+            let transaction = transaction.borrow();
+            let (mark, code) = match transaction.tx_type {
+                TransactionType::Deposit => ("C", "NMSC"),
+                TransactionType::Withdrawal => ("D", "NMSC"),
+                TransactionType::Transfer => ("D", "NTRF"),
+            };
+            balance += if mark == "C" { 1 } else { -1 } * i128::from(transaction.amount);
+            closing_date = transaction.timestamp;
+
+            let reference = transaction.tx_id.to_string();
+            // The reference for the account owner has at most 16 characters.
+            let reference = if reference.len() <= 16 {
+                reference
+            } else {
+                "NONREF".to_string()
+            };
+            let date = format_date(transaction.timestamp);
+            write!(
+                buf_writer,
+                ":61:{}{}{}{}{}{}\r\n",
+                date,
+                &date[2..],
+                mark,
+                format_amount(u128::from(transaction.amount))?,
+                code,
+                reference
+            )?;
+
+            for (index, line) in narrative_lines(&transaction.description)?
+                .iter()
+                .enumerate()
+            {
+                let tag = if index == 0 { ":86:" } else { "" };
+                write!(buf_writer, "{}{}\r\n", tag, line)?;
+            }
+        }
+
+        write!(
+            buf_writer,
+            ":62F:{}{}{}{}\r\n",
+            if balance < 0 { "D" } else { "C" },
+            format_date(closing_date),
+            CURRENCY,
+            format_amount(balance.unsigned_abs())?
+        )?;
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The `Mt940Reader` struct reads transactions from the fields of MT940 statements.
+struct Mt940Reader<B: BufRead> {
+    lines: Lines<B>,
+    lookahead: Option<String>,
+    pending: Option<Transaction>,
+    described: bool,
+    balance: Option<i128>,
+    position: u64,
+}
+
+impl<B: BufRead> Mt940Reader<B> {
+    /// Reads fields up to the end of the next statement line and its narrative.
+    fn read_record(&mut self) -> Result<Option<Transaction>, ParserError> {
+        while let Some((tag, lines)) = self.next_field()? {
+            if tag == "86" {
+                if let Some(transaction) = &mut self.pending
+                    && !self.described
+                {
+                    transaction.description = lines.concat();
+                    self.described = true;
+                }
+                continue;
+            }
+
+            let ready = self.pending.take();
+            match tag.as_str() {
+                "61" => {
+                    self.pending = Some(self.parse_statement_line(&lines[0])?);
+                    self.described = false;
+                }
+                "60F" | "60M" => self.balance = Some(parse_balance(&lines[0])?),
+                "62F" | "62M" => {
+                    let closing = parse_balance(&lines[0])?;
+                    if let Some(balance) = self.balance.take()
+                        && balance != closing
+                    {
+                        return Err(ParserError::InvalidMt940Format(format!(
+                            "Closing balance {} does not match the transactions",
+                            lines[0]
+                        )));
+                    }
+                }
+                _ => {}
+            }
+            if ready.is_some() {
+                return Ok(ready);
+            }
+        }
+        Ok(self.pending.take())
+    }
+
+    /// Reads the next field as its tag and lines, without the tag.
+    fn next_field(&mut self) -> Result<Option<(String, Vec<String>)>, ParserError> {
+        let mut field: Option<(String, Vec<String>)> = None;
+        loop {
+            let line = match self.lookahead.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    Some(line) => line?.trim_end_matches('\r').to_string(),
+                    None => return Ok(field),
+                },
+            };
+
+            if let Some((tag, value)) = split_tag(&line) {
+                if field.is_some() {
+                    self.lookahead = Some(line);
+                    return Ok(field);
+                }
+                field = Some((tag.to_string(), vec![value.to_string()]));
+            } else if line.trim().is_empty() || line.starts_with('{') || line.starts_with('-') {
+                // Block headers and trailers end the current field.
+                if field.is_some() {
+                    return Ok(field);
+                }
+            } else {
+                match &mut field {
+                    Some((_, lines)) => lines.push(line),
+                    None => return Err(ParserError::InvalidMt940Format(line)),
+                }
+            }
+        }
+    }
+
+    /// Parses a `:61:` statement line and adds its amount to the balance.
+    fn parse_statement_line(&mut self, line: &str) -> Result<Transaction, ParserError> {
+        let invalid = || ParserError::InvalidMt940Format(format!(":61:{}", line));
+        self.position += 1;
+
+        let value_date = line.get(..6).ok_or_else(invalid)?;
+        let (year, value_month, value_day) = parse_date(value_date).ok_or_else(invalid)?;
+        let mut rest = &line[6..];
+        let mut date = days_from_civil(year, value_month, value_day);
+        if let Some(entry_date) = rest
+            .get(..4)
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+        {
+            let (month, day) = (
+                entry_date[..2].parse::<u32>().unwrap(),
+                entry_date[2..].parse::<u32>().unwrap(),
+            );
+            if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+                return Err(invalid());
+            }
+            // The entry date may fall into the next or the previous year of the value date.
+            let year = match (value_month, month) {
+                (12, 1) => year + 1,
+                (1, 12) => year - 1,
+                _ => year,
+            };
+            date = days_from_civil(year, month, day);
+            rest = &rest[4..];
+        }
+
+        let (credit, mark) = match rest {
+            _ if rest.starts_with("RC") => (false, 2),
+            _ if rest.starts_with("RD") => (true, 2),
+            _ if rest.starts_with('C') => (true, 1),
+            _ if rest.starts_with('D') => (false, 1),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[mark..];
+        // The funds code is the optional third character of the currency code.
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            rest = &rest[1..];
+        }
+
+        let length = rest
+            .find(|c: char| !c.is_ascii_digit() && c != ',')
+            .ok_or_else(invalid)?;
+        let amount = parse_hundredths(&rest[..length], ',').ok_or_else(invalid)?;
+        rest = &rest[length..];
+
+        let code = rest.get(..4).ok_or_else(invalid)?;
+        let reference = rest[4..].split("//").next().unwrap_or_default();
+
+        if let Some(balance) = &mut self.balance {
+            *balance += if credit { 1 } else { -1 } * i128::from(amount);
+        }
+        let tx_type = match (code, credit) {
+            ("NTRF", _) => TransactionType::Transfer,
+            (_, true) => TransactionType::Deposit,
+            (_, false) => TransactionType::Withdrawal,
+        };
+        let timestamp = u64::try_from(date * 86_400_000).map_err(|_| invalid())?;
+
+        Ok(Transaction::new(
+            reference.parse::<u64>().unwrap_or(self.position),
+            tx_type,
+            0,
+            0,
+            amount,
+            timestamp,
+            TransactionStatus::Success,
+            String::new(),
+        ))
+    }
+}
+
+/// Formats an amount in hundredths, failing when it is longer than an MT940 amount.
+fn format_amount(amount: u128) -> Result<String, ParserError> {
+    let formatted = format_hundredths(amount, ',');
+    if formatted.len() > AMOUNT_LENGTH {
+        return Err(ParserError::InvalidMt940Format(format!(
+            "Too large amount: {}",
+            formatted
+        )));
+    }
+    Ok(formatted)
+}
+
+/// Wraps a description into narrative lines that read back as the same description.
+///
+/// The concatenated lines are the description, so lines are broken early where a continuation
+/// line would start with a character that ends the field or starts a new one when read.
+fn narrative_lines(description: &str) -> Result<Vec<String>, ParserError> {
+    let narrative = description
+        .replace(['\r', '\n'], " ")
+        .chars()
+        .collect::<Vec<_>>();
+    let starts_line = |c: char| !matches!(c, '-' | '{' | ':') && !c.is_whitespace();
+    let too_long = || {
+        ParserError::InvalidMt940Format(format!(
+            "Description does not fit into {} narrative lines: {}",
+            NARRATIVE_LINES, description
+        ))
+    };
+
+    let mut lines = vec![];
+    let mut start = 0;
+    while start < narrative.len() {
+        let end = (start + 1..=(start + NARRATIVE_LINE).min(narrative.len()))
+            .rev()
+            .find(|end| *end == narrative.len() || starts_line(narrative[*end]))
+            .ok_or_else(too_long)?;
+        lines.push(narrative[start..end].iter().collect::<String>());
+        start = end;
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    if lines.len() > NARRATIVE_LINES {
+        return Err(too_long());
+    }
+    Ok(lines)
+}
+
+/// Splits a line like `:61:...` into its tag and value.
+fn split_tag(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let (tag, value) = rest.split_once(':')?;
+    ((2..=3).contains(&tag.len()) && tag.bytes().all(|b| b.is_ascii_alphanumeric()))
+        .then_some((tag, value))
+}
+
+/// Parses a `YYMMDD` date into a year, month and day.
+fn parse_date(s: &str) -> Option<(i64, u32, u32)> {
+    if s.len() != 6 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year = s[..2].parse::<i64>().ok()?;
+    let month = s[2..4]
+        .parse::<u32>()
+        .ok()
+        .filter(|m| (1..=12).contains(m))?;
+    let day = s[4..]
+        .parse::<u32>()
+        .ok()
+        .filter(|d| (1..=31).contains(d))?;
+    Some((if year < 70 { 2000 } else { 1900 } + year, month, day))
+}
+
+/// Formats the date of a timestamp as `YYMMDD`.
+fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86_400_000) as i64);
+    format!("{:02}{:02}{:02}", year % 100, month, day)
+}
+
+/// Parses a balance like `C211001EUR1000,00` into signed hundredths.
+fn parse_balance(s: &str) -> Result<i128, ParserError> {
+    let invalid = || ParserError::InvalidMt940Format(format!("Invalid balance: {}", s));
+    let sign = match s.get(..1) {
+        Some("C") => 1,
+        Some("D") => -1,
+        _ => return Err(invalid()),
+    };
+    let amount = s
+        .get(10..)
+        .and_then(|amount| parse_hundredths(amount, ','))
+        .ok_or_else(invalid)?;
+    Ok(sign * i128::from(amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_mt940() {
+        let mut file = File::open("../samples/mt940_example.mt940").unwrap();
+        let transactions = Mt940Parser.read_from(&mut file).unwrap();
+        assert_eq!(transactions.len(), 3);
+
+        assert_eq!(transactions[0].tx_id, 1000000000000001);
+        assert_eq!(transactions[0].tx_type, TransactionType::Transfer);
+        assert_eq!(transactions[0].amount, 15025);
+        assert_eq!(transactions[0].timestamp, 1633046400000);
+        assert_eq!(transactions[0].description, "Invoice 42 from customer");
+
+        assert_eq!(transactions[1].tx_id, 2);
+        assert_eq!(transactions[1].tx_type, TransactionType::Withdrawal);
+        assert_eq!(transactions[1].amount, 2050);
+        assert_eq!(
+            transactions[1].description,
+            "Card payment at the shop around the corner, paid with the card ending 1234"
+        );
+
+        assert_eq!(transactions[2].tx_type, TransactionType::Withdrawal);
+        assert_eq!(transactions[2].amount, 700);
+        assert_eq!(transactions[2].timestamp, 1633305600000);
+        assert_eq!(transactions[2].description, "");
+    }
+
+    #[test]
+    fn it_reads_written_mt940() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        Mt940Parser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(
+            buffer.starts_with(
+                b":20:STATEMENT\r\n:25:NOTPROVIDED\r\n:28C:1\r\n:60F:C210930XXX0,00\r\n"
+            )
+        );
+
+        let read = Mt940Parser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read.len(), transactions.len());
+        for (read, written) in read.iter().zip(&transactions) {
+            assert_eq!(read.tx_id, written.tx_id);
+            assert_eq!(read.tx_type, written.tx_type);
+            assert_eq!(read.amount, written.amount);
+            assert_eq!(read.timestamp, written.timestamp / 86_400_000 * 86_400_000);
+            assert_eq!(read.description, written.description);
+        }
+    }
+
+    #[test]
+    fn it_wraps_mt940_narratives() {
+        // The second line would start with " - " when split after 65 characters.
+        let description = format!("{} - {{x}} :86:{}", "a".repeat(63), "b".repeat(200));
+        let transaction = Transaction {
+            description: description.clone(),
+            ..Transaction::default()
+        };
+        let mut buffer = vec![];
+        Mt940Parser.write_to(&mut buffer, &[transaction]).unwrap();
+        let read = Mt940Parser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read[0].description, description);
+
+        for transaction in [
+            Transaction {
+                description: "a".repeat(391),
+                ..Transaction::default()
+            },
+            Transaction {
+                description: format!("a{}", "-".repeat(70)),
+                ..Transaction::default()
+            },
+            Transaction {
+                amount: 1_000_000_000_000_000,
+                ..Transaction::default()
+            },
+        ] {
+            assert!(
+                Mt940Parser
+                    .write_to(&mut vec![], &[transaction])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidMt940Format(_)))
+            );
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_mt940_format() {
+        for mt940 in [
+            "text before a tag",
+            ":61:211301C1,00NMSCNONREF",
+            ":61:211001X1,00NMSCNONREF",
+            ":61:211001C1,001NMSCNONREF",
+            ":61:211001C1,00",
+            ":60F:C211001EUR1,00\n:61:211001C1,00NMSCNONREF\n:62F:C211001EUR1,50",
+        ] {
+            assert!(
+                Mt940Parser
+                    .read_from(&mut mt940.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidMt940Format(_))),
+                "{}",
+                mt940
+            );
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//! The `ofx_parser` module provides functionality to parse financial transactions from OFX files.
+//!
+//! Both SGML (OFX 1.x) and XML (OFX 2.x) files are read, one `STMTTRN` entry at a time.
+//! Fields are mapped as follows:
+//! - `TX_ID`: the `FITID` when it is numeric, otherwise the position of the transaction in the
+//!   file, starting from 1.
+//! - `TX_TYPE`: `TRANSFER` for the `XFER` transaction type, otherwise `DEPOSIT` for a positive
+//!   and `WITHDRAWAL` for a negative `TRNAMT`.
+//! - `FROM_USER_ID`, `TO_USER_ID`: always 0.
+//! - `AMOUNT`: the absolute `TRNAMT` in hundredths (cents).
+//! - `TIMESTAMP`: `DTPOSTED` in milliseconds since the Unix epoch.
+//! - `STATUS`: always `SUCCESS`.
+//! - `DESCRIPTION`: the `MEMO`, or the `NAME` without a memo.
+//!
+//! Transactions are written as an OFX 1.0.2 bank statement, with transfers as negative amounts
+//! and a ledger balance computed from the amounts. The statement is written in memory, as its
+//! date range precedes the transactions, and declares `ENCODING:UTF-8` as descriptions are
+//! written as UTF-8.
+
+use crate::date::{civil_from_days, days_from_civil};
+use crate::decimal::{format_hundredths, parse_hundredths};
+use crate::error::ParserError;
+use crate::transaction::{Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+/// The `OfxParserFactory` struct is a factory for creating OFX parsers.
+pub struct OfxParserFactory;
+
+impl ParserFactory for OfxParserFactory {
+    type Parser = OfxParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        OfxParser
+    }
+}
+
+/// The `OfxParser` struct is a parser for OFX files.
+pub struct OfxParser;
+
+impl Parser for OfxParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        let start = transactions.iter().map(|t| t.timestamp).min().unwrap_or(0);
+        let end = transactions.iter().map(|t| t.timestamp).max().unwrap_or(0);
+        let mut balance: i128 = 0;
+
+        let mut buf_writer = BufWriter::new(writer);
+        buf_writer.write_all(
+            b"OFXHEADER:100\r\nDATA:OFXSGML\r\nVERSION:102\r\nSECURITY:NONE\r\n\
+              ENCODING:UTF-8\r\nCHARSET:NONE\r\nCOMPRESSION:NONE\r\nOLDFILEUID:NONE\r\n\
+              NEWFILEUID:NONE\r\n\r\n",
+        )?;
+        write!(
+            buf_writer,
+            "<OFX>\r\n<SIGNONMSGSRSV1>\r\n<SONRS>\r\n<STATUS>\r\n<CODE>0\r\n<SEVERITY>INFO\r\n\
+             </STATUS>\r\n<DTSERVER>{}\r\n<LANGUAGE>ENG\r\n</SONRS>\r\n</SIGNONMSGSRSV1>\r\n\
+             <BANKMSGSRSV1>\r\n<STMTTRNRS>\r\n<TRNUID>0\r\n<STATUS>\r\n<CODE>0\r\n\
+             <SEVERITY>INFO\r\n</STATUS>\r\n<STMTRS>\r\n<CURDEF>XXX\r\n<BANKACCTFROM>\r\n\
+             <BANKID>NOTPROVIDED\r\n<ACCTID>NOTPROVIDED\r\n<ACCTTYPE>CHECKING\r\n\
+             </BANKACCTFROM>\r\n<BANKTRANLIST>\r\n<DTSTART>{}\r\n<DTEND>{}\r\n",
+            format_date_time(end),
+            format_date_time(start),
+            format_date_time(end)
+        )?;
+
+        for transaction in transactions {
+            let (trntype, sign) = match transaction.tx_type {
+                TransactionType::Deposit => ("CREDIT", ""),
+                TransactionType::Withdrawal => ("DEBIT", "-"),
+                TransactionType::Transfer => ("XFER", "-"),
+            };
+            balance += if sign.is_empty() { 1 } else { -1 } * i128::from(transaction.amount);
+            write!(
+                buf_writer,
+                "<STMTTRN>\r\n<TRNTYPE>{}\r\n<DTPOSTED>{}\r\n<TRNAMT>{}{}\r\n<FITID>{}\r\n",
+                trntype,
+                format_date_time(transaction.timestamp),
+                sign,
+                format_hundredths(u128::from(transaction.amount), '.'),
+                transaction.tx_id
+            )?;
+            if !transaction.description.is_empty() {
+                write!(buf_writer, "<MEMO>{}\r\n", escape(&transaction.description))?;
+            }
+            buf_writer.write_all(b"</STMTTRN>\r\n")?;
+        }
+
+        write!(
+            buf_writer,
+            "</BANKTRANLIST>\r\n<LEDGERBAL>\r\n<BALAMT>{}{}\r\n<DTASOF>{}\r\n</LEDGERBAL>\r\n\
+             </STMTRS>\r\n</STMTTRNRS>\r\n</BANKMSGSRSV1>\r\n</OFX>\r\n",
+            if balance < 0 { "-" } else { "" },
+            format_hundredths(balance.unsigned_abs(), '.'),
+            format_date_time(end)
+        )?;
+        buf_writer.flush()?;
+        Ok(())
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut tokenizer = Tokenizer {
+            reader: BufReader::new(r),
+            in_tag: false,
+        };
+        let mut position = 0;
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut tokenizer, &mut position).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+}
+
+impl OfxParser {
+    /// Reads tags up to the end of the next `STMTTRN` entry.
+    fn read_record<B: BufRead>(
+        tokenizer: &mut Tokenizer<B>,
+        position: &mut u64,
+    ) -> Result<Option<Transaction>, ParserError> {
+        loop {
+            match tokenizer.next_token()? {
+                Some(Token::Start(name)) if name == "STMTTRN" => break,
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+
+        let mut values: Vec<(String, String)> = vec![];
+        let mut element = None;
+        loop {
+            match tokenizer.next_token()? {
+                Some(Token::Start(name)) => element = Some(name),
+                Some(Token::Text(text)) => {
+                    if let Some(name) = element.take() {
+                        values.push((name, unescape(text.trim())));
+                    }
+                }
+                Some(Token::End(name)) if name == "STMTTRN" => break,
+                Some(Token::End(_)) => element = None,
+                None => {
+                    return Err(ParserError::InvalidOfxFormat(
+                        "Unexpected end of file in STMTTRN".to_string(),
+                    ));
+                }
+            }
+        }
+
+        *position += 1;
+        let value = |name: &str| {
+            values
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let required = |name: &str| {
+            value(name).ok_or_else(|| ParserError::InvalidOfxFormat(format!("Missing {}", name)))
+        };
+
+        let trnamt = required("TRNAMT")?;
+        let (negative, amount) = match trnamt.strip_prefix('-') {
+            Some(amount) => (true, amount),
+            None => (false, trnamt.strip_prefix('+').unwrap_or(trnamt)),
+        };
+        let amount = parse_hundredths(amount, '.')
+            .ok_or_else(|| ParserError::InvalidOfxFormat(format!("Invalid TRNAMT: {}", trnamt)))?;
+        let tx_type = match (value("TRNTYPE"), negative) {
+            (Some("XFER"), _) => TransactionType::Transfer,
+            (_, false) => TransactionType::Deposit,
+            (_, true) => TransactionType::Withdrawal,
+        };
+        let dtposted = required("DTPOSTED")?;
+        let timestamp = parse_date_time(dtposted).ok_or_else(|| {
+            ParserError::InvalidOfxFormat(format!("Invalid DTPOSTED: {}", dtposted))
+        })?;
+
+        Ok(Some(Transaction::new(
+            value("FITID")
+                .and_then(|fitid| fitid.parse::<u64>().ok())
+                .unwrap_or(*position),
+            tx_type,
+            0,
+            0,
+            amount,
+            timestamp,
+            TransactionStatus::Success,
+            value("MEMO")
+                .or(value("NAME"))
+                .unwrap_or_default()
+                .to_string(),
+        )))
+    }
+}
+
+/// The `Token` enum represents a tag or the text between tags.
+enum Token {
+    Start(String),
+    End(String),
+    Text(String),
+}
+
+/// The `Tokenizer` struct splits SGML or XML into tags and text, skipping processing
+/// instructions, declarations and whitespace between tags.
+struct Tokenizer<B: BufRead> {
+    reader: B,
+    in_tag: bool,
+}
+
+impl<B: BufRead> Tokenizer<B> {
+    fn next_token(&mut self) -> Result<Option<Token>, ParserError> {
+        loop {
+            if self.in_tag {
+                self.in_tag = false;
+                match self.read_tag()? {
+                    Some(token) => return Ok(Some(token)),
+                    None => continue,
+                }
+            }
+
+            let mut bytes = vec![];
+            self.reader.read_until(b'<', &mut bytes)?;
+            if bytes.is_empty() {
+                return Ok(None);
+            }
+            if bytes.last() == Some(&b'<') {
+                bytes.pop();
+                self.in_tag = true;
+            }
+            if !bytes.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(Token::Text(
+                    String::from_utf8_lossy(&bytes).into_owned(),
+                )));
+            }
+        }
+    }
+
+    fn read_tag(&mut self) -> Result<Option<Token>, ParserError> {
+        let mut bytes = vec![];
+        self.reader.read_until(b'>', &mut bytes)?;
+        if bytes.pop() != Some(b'>') {
+            return Err(ParserError::InvalidOfxFormat(
+                "Unterminated tag".to_string(),
+            ));
+        }
+        let tag = String::from_utf8_lossy(&bytes).trim().to_ascii_uppercase();
+        Ok(match tag.strip_prefix('/') {
+            _ if tag.is_empty() || tag.starts_with(['?', '!']) => None,
+            Some(name) => Some(Token::End(name.trim().to_string())),
+            None => Some(Token::Start(tag)),
+        })
+    }
+}
+
+/// Parses an OFX date and time, e.g. `20211001093000.000[-5:EST]`.
+fn parse_date_time(s: &str) -> Option<u64> {
+    let (date_time, zone) = match s.split_once('[') {
+        Some((date_time, zone)) => (date_time.trim(), Some(zone.trim_end_matches(']'))),
+        None => (s.trim(), None),
+    };
+    let (digits, fraction) = date_time.split_once('.').unwrap_or((date_time, ""));
+    if digits.len() < 8 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        match digits.get(range) {
+            Some(part) => part.parse().ok(),
+            None => Some(0),
+        }
+    };
+    let (month, day) = (number(4..6)? as u32, number(6..8)? as u32);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut milliseconds = days_from_civil(number(0..4)?, month, day) * 86_400_000
+        + (number(8..10)? * 3_600 + number(10..12)? * 60 + number(12..14)?) * 1_000;
+    if !fraction.is_empty() {
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        milliseconds += format!("{:0<3}", &fraction[..fraction.len().min(3)])
+            .parse::<i64>()
+            .ok()?;
+    }
+    if let Some(zone) = zone {
+        // The offset from GMT is given in hours, possibly with a fraction.
+        let offset = zone.split(':').next()?.trim();
+        let hours = offset.parse::<f64>().ok()?;
+        milliseconds -= (hours * 3_600_000.0).round() as i64;
+    }
+    u64::try_from(milliseconds).ok()
+}
+
+/// Formats a timestamp as an OFX date and time in GMT.
+fn format_date_time(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86_400_000) as i64);
+    let milliseconds = timestamp % 86_400_000;
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}.{:03}[0:GMT]",
+        year,
+        month,
+        day,
+        milliseconds / 3_600_000,
+        milliseconds / 60_000 % 60,
+        milliseconds / 1_000 % 60,
+        milliseconds % 1_000
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\r', '\n'], " ")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_ofx() {
+        let sgml = "OFXHEADER:100\r\nDATA:OFXSGML\r\n\r\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\
+            <BANKTRANLIST><DTSTART>20211001\r\n\
+            <STMTTRN>\r\n<TRNTYPE>DEBIT\r\n<DTPOSTED>20211001093000.5[-5:EST]\r\n\
+            <TRNAMT>-20.5\r\n<FITID>ABC-1\r\n<NAME>Shop &amp; Co\r\n</STMTTRN>\r\n\
+            <STMTTRN><TRNTYPE>XFER<DTPOSTED>20211002<TRNAMT>100.00<FITID>42\
+            <NAME>Bank<MEMO>Savings</STMTTRN>\
+            </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+        let xml = "<?xml version=\"1.0\"?>\n<?OFX OFXHEADER=\"200\" VERSION=\"220\"?>\n\
+            <OFX><STMTTRN><TRNTYPE>CREDIT</TRNTYPE><DTPOSTED>20211001</DTPOSTED>\
+            <TRNAMT>+1.25</TRNAMT><FITID>7</FITID><MEMO>Interest</MEMO></STMTTRN></OFX>";
+
+        let transactions = OfxParser.read_from(&mut sgml.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].tx_id, 1);
+        assert_eq!(transactions[0].tx_type, TransactionType::Withdrawal);
+        assert_eq!(transactions[0].amount, 2050);
+        assert_eq!(transactions[0].timestamp, 1633098600500);
+        assert_eq!(transactions[0].description, "Shop & Co");
+        assert_eq!(transactions[1].tx_id, 42);
+        assert_eq!(transactions[1].tx_type, TransactionType::Transfer);
+        assert_eq!(transactions[1].timestamp, 1633132800000);
+        assert_eq!(transactions[1].description, "Savings");
+
+        let transactions = OfxParser.read_from(&mut xml.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].tx_type, TransactionType::Deposit);
+        assert_eq!(transactions[0].amount, 125);
+        assert_eq!(transactions[0].description, "Interest");
+    }
+
+    #[test]
+    fn it_reads_written_ofx() {
+        let mut transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        transactions[0].description = "<a & b>".to_string();
+        transactions[1].description = "Café Zürich — 10 €".to_string();
+        let mut buffer = vec![];
+        OfxParser.write_to(&mut buffer, &transactions).unwrap();
+        let header = String::from_utf8_lossy(&buffer[..120]);
+        assert!(header.contains("\r\nENCODING:UTF-8\r\nCHARSET:NONE\r\n"));
+
+        let read = OfxParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read.len(), transactions.len());
+        for (read, written) in read.iter().zip(&transactions) {
+            assert_eq!(read.tx_id, written.tx_id);
+            assert_eq!(read.tx_type, written.tx_type);
+            assert_eq!(read.amount, written.amount);
+            assert_eq!(read.timestamp, written.timestamp);
+            assert_eq!(read.description, written.description);
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_ofx_format() {
+        for ofx in [
+            "<STMTTRN><DTPOSTED>20211001<TRNAMT>1.00",
+            "<STMTTRN><DTPOSTED>20211001</STMTTRN>",
+            "<STMTTRN><DTPOSTED>20211001<TRNAMT>1.001</STMTTRN>",
+            "<STMTTRN><DTPOSTED>20211301<TRNAMT>1.00</STMTTRN>",
+            "<STMTTRN><DTPOSTED>20211001<TRNAMT>1.00</STMTTRN",
+        ] {
+            assert!(
+                OfxParser
+                    .read_from(&mut ofx.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidOfxFormat(_))),
+                "{}",
+                ofx
+            );
+        }
+    }
+}
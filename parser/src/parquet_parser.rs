@@ -0,0 +1,254 @@
+//! The `parquet_parser` module provides functionality to parse financial transactions from
+//! Apache Parquet files.
+//!
+//! Transactions are stored with a typed schema, one column per field as described in the
+//! `record_batch` module. Rows are written in row groups of at most `row_group_size`
+//! transactions, and every column is compressed with the selected `ParquetCompression`.
+//! Parquet files are read from memory, as their metadata is at the end of the file.
+
+use crate::error::ParserError;
+use crate::record_batch::{read_record_batches, to_record_batches, transaction_schema};
+use crate::transaction::Transaction;
+use crate::{Parser, ParserFactory, Transactions};
+use bytes::Bytes;
+use parquet::arrow::ArrowWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// The number of transactions converted to a record batch at once.
+const BATCH_SIZE: usize = 8192;
+
+/// The `ParquetCompression` enum represents the compression of Parquet columns.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum ParquetCompression {
+    /// Columns are not compressed.
+    Uncompressed,
+    /// Columns are compressed with Snappy.
+    #[default]
+    Snappy,
+    /// Columns are compressed with gzip.
+    Gzip,
+    /// Columns are compressed with Zstandard.
+    Zstd,
+}
+
+impl FromStr for ParquetCompression {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "uncompressed" | "none" => Ok(ParquetCompression::Uncompressed),
+            "snappy" => Ok(ParquetCompression::Snappy),
+            "gzip" => Ok(ParquetCompression::Gzip),
+            "zstd" => Ok(ParquetCompression::Zstd),
+            _ => Err(ParserError::InvalidValue(format!(
+                "Parquet compression: {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl Display for ParquetCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParquetCompression::Uncompressed => write!(f, "uncompressed"),
+            ParquetCompression::Snappy => write!(f, "snappy"),
+            ParquetCompression::Gzip => write!(f, "gzip"),
+            ParquetCompression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+        }
+    }
+}
+
+/// The `ParquetParserFactory` struct is a factory for creating Parquet parsers.
+pub struct ParquetParserFactory;
+
+impl ParserFactory for ParquetParserFactory {
+    type Parser = ParquetParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        ParquetParser::default()
+    }
+}
+
+/// The `ParquetParser` struct is a parser for Parquet files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParquetParser {
+    row_group_size: usize,
+    compression: ParquetCompression,
+}
+
+impl Default for ParquetParser {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_ROW_GROUP_SIZE, ParquetCompression::default())
+    }
+}
+
+impl Parser for ParquetParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, mut r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut buffer = vec![];
+        if let Err(e) = r.read_to_end(&mut buffer) {
+            return Box::new(std::iter::once(Err(e.into())));
+        }
+        let batches = match ParquetRecordBatchReaderBuilder::try_new(Bytes::from(buffer))
+            .and_then(|builder| builder.with_batch_size(BATCH_SIZE).build())
+        {
+            Ok(batches) => batches,
+            Err(e) => return Box::new(std::iter::once(Err(invalid(e.to_string())))),
+        };
+
+        read_record_batches(batches, invalid)
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl ParquetParser {
+    /// The default maximum number of transactions in a row group.
+    pub const DEFAULT_ROW_GROUP_SIZE: usize = 1024 * 1024;
+
+    /// Creates a parser for Parquet files with the given row group size and compression.
+    pub fn new(row_group_size: usize, compression: ParquetCompression) -> Self {
+        Self {
+            row_group_size: row_group_size.max(1),
+            compression,
+        }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(self.row_group_size)
+            .set_compression(self.compression.into())
+            .build();
+        // The Parquet writer needs a `Send` writer, so row groups are written to a buffer
+        // and moved to the writer as soon as they are complete.
+        let mut parquet_writer =
+            ArrowWriter::try_new(vec![], transaction_schema(), Some(properties))
+                .map_err(|e| invalid(e.to_string()))?;
+
+        for batch in to_record_batches(transactions, BATCH_SIZE) {
+            parquet_writer
+                .write(&batch?)
+                .map_err(|e| invalid(e.to_string()))?;
+            writer.write_all(&std::mem::take(parquet_writer.inner_mut()))?;
+        }
+
+        parquet_writer
+            .finish()
+            .map_err(|e| invalid(e.to_string()))?;
+        writer.write_all(parquet_writer.inner_mut())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidParquetFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_parquet() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        for compression in ["uncompressed", "snappy", "gzip", "zstd"] {
+            let parser = ParquetParser::new(300, compression.parse().unwrap());
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &transactions).unwrap();
+            assert!(buffer.starts_with(b"PAR1") && buffer.ends_with(b"PAR1"));
+
+            let metadata = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(buffer.clone()))
+                .unwrap()
+                .metadata()
+                .clone();
+            assert_eq!(metadata.num_row_groups(), 4);
+            assert_eq!(
+                metadata.row_group(0).column(0).compression(),
+                parser.compression.into()
+            );
+
+            let read = parser.read_from(&mut buffer.as_slice()).unwrap();
+            assert_eq!(read, transactions);
+        }
+
+        let mut buffer = vec![];
+        ParquetParser::default().write_to(&mut buffer, &[]).unwrap();
+        assert!(
+            ParquetParser::default()
+                .read_from(&mut buffer.as_slice())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_parquet_format() {
+        let mut buffer = vec![];
+        ParquetParser::default()
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+        let truncated = &buffer[..buffer.len() - 10];
+
+        for parquet in [b"PAR1 not parquet".as_slice(), truncated] {
+            assert!(
+                ParquetParser::default()
+                    .read_from(&mut &parquet[..])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidParquetFormat(_)))
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! The `proto` module contains the types generated from the Protocol Buffers schema in
+//! `proto/transaction.proto` (package `transaction.v1`) and their conversions to and from the
+//! transaction types of this crate.
+//!
+//! Converting a message fails when its type or status is unspecified or unknown, as every
+//! transaction of this crate has both.
+
+use crate::error::ParserError;
+use crate::transaction;
+
+include!(concat!(env!("OUT_DIR"), "/transaction.v1.rs"));
+
+impl From<transaction::TransactionType> for TransactionType {
+    fn from(tx_type: transaction::TransactionType) -> Self {
+        match tx_type {
+            transaction::TransactionType::Deposit => TransactionType::Deposit,
+            transaction::TransactionType::Withdrawal => TransactionType::Withdrawal,
+            transaction::TransactionType::Transfer => TransactionType::Transfer,
+        }
+    }
+}
+
+impl TryFrom<TransactionType> for transaction::TransactionType {
+    type Error = ParserError;
+    fn try_from(tx_type: TransactionType) -> Result<Self, Self::Error> {
+        match tx_type {
+            TransactionType::Deposit => Ok(transaction::TransactionType::Deposit),
+            TransactionType::Withdrawal => Ok(transaction::TransactionType::Withdrawal),
+            TransactionType::Transfer => Ok(transaction::TransactionType::Transfer),
+            TransactionType::Unspecified => Err(ParserError::UnknownTransactionType(
+                tx_type.as_str_name().to_string(),
+            )),
+        }
+    }
+}
+
+impl From<transaction::TransactionStatus> for TransactionStatus {
+    fn from(status: transaction::TransactionStatus) -> Self {
+        match status {
+            transaction::TransactionStatus::Success => TransactionStatus::Success,
+            transaction::TransactionStatus::Failure => TransactionStatus::Failure,
+            transaction::TransactionStatus::Pending => TransactionStatus::Pending,
+        }
+    }
+}
+
+impl TryFrom<TransactionStatus> for transaction::TransactionStatus {
+    type Error = ParserError;
+    fn try_from(status: TransactionStatus) -> Result<Self, Self::Error> {
+        match status {
+            TransactionStatus::Success => Ok(transaction::TransactionStatus::Success),
+            TransactionStatus::Failure => Ok(transaction::TransactionStatus::Failure),
+            TransactionStatus::Pending => Ok(transaction::TransactionStatus::Pending),
+            TransactionStatus::Unspecified => Err(ParserError::UnknownTransactionStatus(
+                status.as_str_name().to_string(),
+            )),
+        }
+    }
+}
+
+impl From<&transaction::Transaction> for Transaction {
+    fn from(transaction: &transaction::Transaction) -> Self {
+        Self {
+            tx_id: transaction.tx_id,
+            tx_type: TransactionType::from(transaction.tx_type).into(),
+            from_user_id: transaction.from_user_id,
+            to_user_id: transaction.to_user_id,
+            amount: transaction.amount,
+            timestamp: transaction.timestamp,
+            status: TransactionStatus::from(transaction.status).into(),
+            description: transaction.description.clone(),
+        }
+    }
+}
+
+impl TryFrom<Transaction> for transaction::Transaction {
+    type Error = ParserError;
+    fn try_from(message: Transaction) -> Result<Self, Self::Error> {
+        let tx_type = TransactionType::try_from(message.tx_type)
+            .map_err(|_| ParserError::UnknownTransactionType(message.tx_type.to_string()))?;
+        let status = TransactionStatus::try_from(message.status)
+            .map_err(|_| ParserError::UnknownTransactionStatus(message.status.to_string()))?;
+        Ok(Self {
+            tx_id: message.tx_id,
+            tx_type: tx_type.try_into()?,
+            from_user_id: message.from_user_id,
+            to_user_id: message.to_user_id,
+            amount: message.amount,
+            timestamp: message.timestamp,
+            status: status.try_into()?,
+            description: message.description,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_protobuf_messages() {
+        let transaction = transaction::Transaction::new(
+            1000000000000000,
+            transaction::TransactionType::Transfer,
+            9223372036854775807,
+            1,
+            100,
+            1633036860000,
+            transaction::TransactionStatus::Pending,
+            "Record number 1".to_string(),
+        );
+        let message = Transaction::from(&transaction);
+        assert_eq!(message.tx_type(), TransactionType::Transfer);
+        assert_eq!(message.status(), TransactionStatus::Pending);
+        assert_eq!(
+            transaction::Transaction::try_from(message.clone()).unwrap(),
+            transaction
+        );
+
+        let unspecified = Transaction {
+            tx_type: TransactionType::Unspecified.into(),
+            ..message.clone()
+        };
+        let unknown = Transaction {
+            status: 4,
+            ..message
+        };
+        assert!(
+            transaction::Transaction::try_from(unspecified)
+                .is_err_and(|e| matches!(e, ParserError::UnknownTransactionType(_)))
+        );
+        assert!(
+            transaction::Transaction::try_from(unknown)
+                .is_err_and(|e| matches!(e, ParserError::UnknownTransactionStatus(_)))
+        );
+    }
+}
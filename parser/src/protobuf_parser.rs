@@ -0,0 +1,184 @@
+//! The `protobuf_parser` module provides functionality to parse financial transactions from
+//! length-delimited Protocol Buffers streams.
+//!
+//! Every transaction is a `transaction.v1.Transaction` message of the schema in
+//! `proto/transaction.proto`, prefixed with its length in bytes as a varint, so transactions are
+//! read and written one at a time.
+
+use crate::error::ParserError;
+use crate::proto;
+use crate::transaction::Transaction;
+use crate::{Parser, ParserFactory, Transactions};
+use prost::Message;
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+/// The `ProtobufParserFactory` struct is a factory for creating Protocol Buffers parsers.
+pub struct ProtobufParserFactory;
+
+impl ParserFactory for ProtobufParserFactory {
+    type Parser = ProtobufParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        ProtobufParser
+    }
+}
+
+/// The `ProtobufParser` struct is a parser for length-delimited Protocol Buffers streams.
+pub struct ProtobufParser;
+
+impl Parser for ProtobufParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = BufReader::new(r);
+        let mut buffer = vec![];
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut reader, &mut buffer).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl ProtobufParser {
+    fn read_record<R: BufRead>(
+        reader: &mut R,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<Transaction>, ParserError> {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let len = Self::read_length(reader)?;
+        buffer.clear();
+        reader.take(len).read_to_end(buffer)?;
+        if (buffer.len() as u64) < len {
+            return Err(invalid(format!(
+                "Message of {} bytes truncated to {}",
+                len,
+                buffer.len()
+            )));
+        }
+
+        let message =
+            proto::Transaction::decode(buffer.as_slice()).map_err(|e| invalid(e.to_string()))?;
+        Transaction::try_from(message).map(Some)
+    }
+
+    /// Reads the varint length prefix of a message.
+    fn read_length<R: BufRead>(reader: &mut R) -> Result<u64, ParserError> {
+        let mut len = 0;
+        for shift in (0..64).step_by(7) {
+            let mut byte = [0];
+            reader
+                .read_exact(&mut byte)
+                .map_err(|_| invalid("Truncated message length".to_string()))?;
+            len |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(len);
+            }
+        }
+        Err(invalid("Invalid message length".to_string()))
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        let mut buffer = vec![];
+        for transaction in transactions {
+            buffer.clear();
+            proto::Transaction::from(transaction?.borrow())
+                .encode_length_delimited(&mut buffer)
+                .map_err(|e| invalid(e.to_string()))?;
+            buf_writer.write_all(&buffer)?;
+        }
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidProtobufFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_protobuf() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        ProtobufParser.write_to(&mut buffer, &transactions).unwrap();
+
+        let mut messages = buffer.as_slice();
+        let first = proto::Transaction::decode_length_delimited(&mut messages).unwrap();
+        assert_eq!(first, proto::Transaction::from(&transactions[0]));
+
+        let read = ProtobufParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, transactions);
+    }
+
+    #[test]
+    fn it_is_invalid_protobuf_format() {
+        let mut buffer = vec![];
+        ProtobufParser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+
+        for protobuf in [
+            b"\x80".as_slice(),
+            &buffer[..buffer.len() - 1],
+            // A tx_id field with the wire type of a 64-bit number but only 2 bytes.
+            b"\x03\x09\x01\x02",
+        ] {
+            assert!(
+                ProtobufParser
+                    .read_from(&mut &protobuf[..])
+                    .is_err_and(|e| matches!(e, ParserError::InvalidProtobufFormat(_))),
+                "{:?}",
+                protobuf
+            );
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! The `qif_parser` module provides functionality to parse financial transactions from QIF files.
+//!
+//! Records of the `!Type:Bank`, `!Type:Cash`, `!Type:CCard`, `!Type:Oth A` and `!Type:Oth L`
+//! sections are read, other sections, such as accounts or investments, are skipped. Each record
+//! is a list of lines starting with a field code and ends with a `^` line. Fields are mapped as
+//! follows:
+//! - `TX_ID`: the `N` number when it is numeric, otherwise the position of the transaction in
+//!   the file, starting from 1.
+//! - `TX_TYPE`: `TRANSFER` for an `L` category in brackets, e.g. `L[Savings]`, otherwise
+//!   `DEPOSIT` for a positive and `WITHDRAWAL` for a negative `T` amount.
+//! - `FROM_USER_ID`, `TO_USER_ID`: always 0.
+//! - `AMOUNT`: the absolute `T` (or `U`) amount in hundredths (cents).
+//! - `TIMESTAMP`: the `D` date at midnight UTC, as `MM/DD/YYYY`, `MM/DD'YY`, `MM/DD/YY` or
+//!   `YYYY-MM-DD`.
+//! - `STATUS`: always `SUCCESS`.
+//! - `DESCRIPTION`: the `M` memo, or the `P` payee without a memo.
+//!
+//! Transactions are written to a `!Type:Bank` section, with transfers as negative amounts to
+//! the `[Transfer]` account.
+
+use crate::date::{civil_from_days, days_from_civil};
+use crate::decimal::{format_hundredths, parse_hundredths};
+use crate::error::ParserError;
+use crate::transaction::{Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
+
+/// The section types that contain bank transactions.
+const BANK_TYPES: [&str; 5] = ["bank", "cash", "ccard", "oth a", "oth l"];
+
+/// The `QifParserFactory` struct is a factory for creating QIF parsers.
+pub struct QifParserFactory;
+
+impl ParserFactory for QifParserFactory {
+    type Parser = QifParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        QifParser
+    }
+}
+
+/// The `QifParser` struct is a parser for QIF files.
+pub struct QifParser;
+
+impl Parser for QifParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut lines = BufReader::new(r).lines();
+        let mut in_bank_section = false;
+        let mut position = 0;
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record =
+                Self::read_record(&mut lines, &mut in_bank_section, &mut position).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl QifParser {
+    /// Reads lines up to the end of the next record of a bank section.
+    fn read_record<B: BufRead>(
+        lines: &mut Lines<B>,
+        in_bank_section: &mut bool,
+        position: &mut u64,
+    ) -> Result<Option<Transaction>, ParserError> {
+        let mut fields: Vec<(char, String)> = vec![];
+
+        for line in lines.by_ref() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+
+            if let Some(header) = line.strip_prefix('!') {
+                let header = header.trim().to_ascii_lowercase();
+                if let Some(section) = header.strip_prefix("type:") {
+                    *in_bank_section = BANK_TYPES.contains(&section.trim());
+                } else if !header.starts_with("option") && !header.starts_with("clear") {
+                    // Account lists and other non-transaction sections.
+                    *in_bank_section = false;
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('^') {
+                if *in_bank_section && !fields.is_empty() {
+                    *position += 1;
+                    return Self::map_record(&fields, *position).map(Some);
+                }
+                fields.clear();
+                continue;
+            }
+
+            let mut chars = line.chars();
+            let code = chars.next().unwrap_or_default();
+            fields.push((code, chars.as_str().trim().to_string()));
+        }
+
+        if *in_bank_section && !fields.is_empty() {
+            return Err(ParserError::InvalidQifFormat(
+                "Record is not terminated by ^".to_string(),
+            ));
+        }
+        Ok(None)
+    }
+
+    fn map_record(fields: &[(char, String)], position: u64) -> Result<Transaction, ParserError> {
+        // Split lines (`S`, `E`, `$`) follow the main fields and are not used.
+        let value = |code: char| {
+            fields
+                .iter()
+                .find(|(c, _)| *c == code)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let total = value('T')
+            .or(value('U'))
+            .ok_or_else(|| ParserError::InvalidQifFormat("Missing amount".to_string()))?;
+        let (negative, amount) = match total.strip_prefix('-') {
+            Some(amount) => (true, amount),
+            None => (false, total.strip_prefix('+').unwrap_or(total)),
+        };
+        let amount = parse_hundredths(&amount.replace(',', ""), '.')
+            .ok_or_else(|| ParserError::InvalidQifFormat(format!("Invalid amount: {}", total)))?;
+        let is_transfer = value('L').is_some_and(|category| category.starts_with('['));
+        let tx_type = match (is_transfer, negative) {
+            (true, _) => TransactionType::Transfer,
+            (false, false) => TransactionType::Deposit,
+            (false, true) => TransactionType::Withdrawal,
+        };
+        let date =
+            value('D').ok_or_else(|| ParserError::InvalidQifFormat("Missing date".to_string()))?;
+        let timestamp = parse_date(date)
+            .ok_or_else(|| ParserError::InvalidQifFormat(format!("Invalid date: {}", date)))?;
+
+        Ok(Transaction::new(
+            value('N')
+                .and_then(|number| number.parse::<u64>().ok())
+                .unwrap_or(position),
+            tx_type,
+            0,
+            0,
+            amount,
+            timestamp,
+            TransactionStatus::Success,
+            value('M').or(value('P')).unwrap_or_default().to_string(),
+        ))
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        buf_writer.write_all(b"!Type:Bank\n")?;
+        for transaction in transactions {
+            let transaction = transaction?;
+            let transaction = transaction.borrow();
+            let (year, month, day) = civil_from_days((transaction.timestamp / 86_400_000) as i64);
+            let sign = match transaction.tx_type {
+                TransactionType::Deposit => "",
+                TransactionType::Withdrawal | TransactionType::Transfer => "-",
+            };
+            write!(
+                buf_writer,
+                "D{:02}/{:02}/{:04}\nT{}{}\nN{}\n",
+                month,
+                day,
+                year,
+                sign,
+                format_hundredths(u128::from(transaction.amount), '.'),
+                transaction.tx_id
+            )?;
+            if !transaction.description.is_empty() {
+                writeln!(
+                    buf_writer,
+                    "M{}",
+                    transaction.description.replace(['\r', '\n'], " ")
+                )?;
+            }
+            if transaction.tx_type == TransactionType::Transfer {
+                buf_writer.write_all(b"L[Transfer]\n")?;
+            }
+            buf_writer.write_all(b"^\n")?;
+        }
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Parses a QIF date into a timestamp at midnight UTC.
+fn parse_date(s: &str) -> Option<u64> {
+    let number = |s: &str| s.trim().parse::<u32>().ok();
+
+    let (year, month, day) = if let Some((year, rest)) = s.split_once('-') {
+        let (month, day) = rest.split_once('-')?;
+        (number(year)?, number(month)?, number(day)?)
+    } else {
+        let (month, rest) = s.split_once('/')?;
+        let (day, year) = rest.split_once(['/', '\''])?;
+        let year = match (number(year)?, year.trim().len()) {
+            // Two-digit years after an apostrophe are in the 2000s, as Quicken writes them.
+            (year, 1..=2) if rest.contains('\'') => 2000 + year,
+            (year, 1..=2) if year < 70 => 2000 + year,
+            (year, 1..=2) => 1900 + year,
+            (year, _) => year,
+        };
+        (year, number(month)?, number(day)?)
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    u64::try_from(days_from_civil(i64::from(year), month, day) * 86_400_000).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_qif() {
+        let qif = "!Account\nNChecking\nTBank\n^\n!Type:Bank\r\n\
+            D10/01/2021\r\nT-1,234.50\r\nPShop\r\nN101\r\n^\r\n\
+            D10/ 2'21\nU100.00\nPEmployer\nMSalary\n^\n\
+            D2021-10-03\nT-5\nL[Savings]\nSGroceries\n$-5\n^\n\
+            !Type:Invst\nD10/04/2021\nT1.00\n^\n";
+        let transactions = QifParser.read_from(&mut qif.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 3);
+
+        assert_eq!(transactions[0].tx_id, 101);
+        assert_eq!(transactions[0].tx_type, TransactionType::Withdrawal);
+        assert_eq!(transactions[0].amount, 123450);
+        assert_eq!(transactions[0].timestamp, 1633046400000);
+        assert_eq!(transactions[0].description, "Shop");
+
+        assert_eq!(transactions[1].tx_id, 2);
+        assert_eq!(transactions[1].tx_type, TransactionType::Deposit);
+        assert_eq!(transactions[1].timestamp, 1633132800000);
+        assert_eq!(transactions[1].description, "Salary");
+
+        assert_eq!(transactions[2].tx_type, TransactionType::Transfer);
+        assert_eq!(transactions[2].amount, 500);
+        assert_eq!(transactions[2].timestamp, 1633219200000);
+    }
+
+    #[test]
+    fn it_reads_written_qif() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        QifParser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(buffer.starts_with(b"!Type:Bank\nD09/30/2021\nT1.00\nN1000000000000000\n"));
+
+        let read = QifParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read.len(), transactions.len());
+        for (read, written) in read.iter().zip(&transactions) {
+            assert_eq!(read.tx_id, written.tx_id);
+            assert_eq!(read.tx_type, written.tx_type);
+            assert_eq!(read.amount, written.amount);
+            assert_eq!(read.timestamp, written.timestamp / 86_400_000 * 86_400_000);
+            assert_eq!(read.description, written.description);
+        }
+    }
+
+    #[test]
+    fn it_is_invalid_qif_format() {
+        for qif in [
+            "!Type:Bank\nD10/01/2021\n^\n",
+            "!Type:Bank\nT1.00\n^\n",
+            "!Type:Bank\nD13/01/2021\nT1.00\n^\n",
+            "!Type:Bank\nD10/01/2021\nT1.001\n^\n",
+            "!Type:Bank\nD10/01/2021\nT1.00\n",
+        ] {
+            assert!(
+                QifParser
+                    .read_from(&mut qif.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidQifFormat(_))),
+                "{}",
+                qif
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! The `record` module collects the fields of a transaction read from a keyed record, such as a
+//! MessagePack or CBOR map or an XML element.
+//!
+//! Keys are the lower-case field names, e.g. `tx_id`, as a JSON object of a transaction would
+//! use them, unless a format names its fields otherwise, e.g. `TxId` in XML. Every field is
+//! required, and each may appear once.
+
+use crate::error::ParserError;
+use crate::transaction::{Field, Transaction};
+
+/// The `Record` struct collects the fields of a transaction being read.
+pub(crate) struct Record {
+    transaction: Transaction,
+    seen: [bool; Field::ALL.len()],
+    invalid: fn(String) -> ParserError,
+    name: fn(Field) -> &'static str,
+}
+
+impl Record {
+    /// Creates an empty record, reporting errors with the `invalid` function of its format.
+    pub(crate) fn new(invalid: fn(String) -> ParserError) -> Self {
+        Self::with_names(invalid, |field| field.key())
+    }
+
+    /// Creates an empty record of a format that names its fields with the `name` function.
+    pub(crate) fn with_names(
+        invalid: fn(String) -> ParserError,
+        name: fn(Field) -> &'static str,
+    ) -> Self {
+        Self {
+            transaction: Transaction::default(),
+            seen: [false; Field::ALL.len()],
+            invalid,
+            name,
+        }
+    }
+
+    /// Returns the field with the given key.
+    pub(crate) fn field(&self, key: &str) -> Result<Field, ParserError> {
+        Field::ALL
+            .into_iter()
+            .find(|field| (self.name)(*field) == key)
+            .ok_or_else(|| (self.invalid)(format!("Unknown field: {}", key)))
+    }
+
+    pub(crate) fn set(&mut self, field: Field, value: &str) -> Result<(), ParserError> {
+        let index = Field::ALL.iter().position(|f| *f == field).unwrap();
+        if self.seen[index] {
+            return Err((self.invalid)(format!(
+                "Duplicate field: {}",
+                (self.name)(field)
+            )));
+        }
+        self.seen[index] = true;
+        field
+            .set_value(&mut self.transaction, value)
+            .map_err(|e| (self.invalid)(e.to_string()))
+    }
+
+    pub(crate) fn finish(self) -> Result<Transaction, ParserError> {
+        match Field::ALL.iter().zip(self.seen).find(|(_, seen)| !seen) {
+            Some((field, _)) => Err((self.invalid)(format!(
+                "Missing field: {}",
+                (self.name)(*field)
+            ))),
+            None => Ok(self.transaction),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! The `record_batch` module converts financial transactions to and from Arrow record batches.
+//!
+//! Every transaction field is a non-nullable column named like the field in lower case, e.g.
+//! `tx_id`. Numbers are `UInt64` columns, `timestamp` is a millisecond timestamp in UTC, and
+//! `tx_type`, `status` and `description` are `Utf8` columns. Batches are handed to Arrow-based
+//! tools, such as DataFusion or Polars, as they are, and the columns of batches read back are
+//! found by name, so they may be in any order and extra columns are ignored.
+
+use crate::Transactions;
+use crate::error::ParserError;
+use crate::transaction::{Field, Transaction};
+use arrow_array::{
+    Array, ArrayRef, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Schema, SchemaRef, TimeUnit};
+use std::borrow::Borrow;
+use std::sync::{Arc, LazyLock};
+
+/// A boxed iterator over record batches of transactions.
+pub type RecordBatches<'a> = Box<dyn Iterator<Item = Result<RecordBatch, ParserError>> + 'a>;
+
+static SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    Arc::new(Schema::new(
+        Field::ALL
+            .iter()
+            .map(|field| arrow_schema::Field::new(field.key(), data_type(field), false))
+            .collect::<Vec<_>>(),
+    ))
+});
+
+/// Returns the Arrow schema of transactions.
+pub fn transaction_schema() -> SchemaRef {
+    SCHEMA.clone()
+}
+
+fn data_type(field: &Field) -> DataType {
+    match field {
+        Field::Timestamp => DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        _ if field.is_numeric() => DataType::UInt64,
+        _ => DataType::Utf8,
+    }
+}
+
+/// Converts transactions to a record batch.
+pub fn to_record_batch<T: Borrow<Transaction>>(
+    transactions: &[T],
+) -> Result<RecordBatch, ParserError> {
+    let transactions = || transactions.iter().map(Borrow::borrow);
+    let columns = Field::ALL
+        .iter()
+        .map(|field| -> ArrayRef {
+            let number = |value: fn(&Transaction) -> u64| {
+                Arc::new(transactions().map(value).collect::<UInt64Array>())
+            };
+            match field {
+                Field::TxId => number(|transaction| transaction.tx_id),
+                Field::FromUserId => number(|transaction| transaction.from_user_id),
+                Field::ToUserId => number(|transaction| transaction.to_user_id),
+                Field::Amount => number(|transaction| transaction.amount),
+                Field::Timestamp => Arc::new(
+                    TimestampMillisecondArray::from_iter_values(
+                        transactions().map(|transaction| transaction.timestamp as i64),
+                    )
+                    .with_timezone("UTC"),
+                ),
+                _ => Arc::new(StringArray::from_iter_values(
+                    transactions().map(|transaction| field.value(transaction)),
+                )),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    RecordBatch::try_new(transaction_schema(), columns)
+        .map_err(|e| ParserError::InvalidArrowFormat(e.to_string()))
+}
+
+/// Converts a record batch to transactions.
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Transaction>, ParserError> {
+    read_record_batch(batch, ParserError::InvalidArrowFormat)
+}
+
+/// Groups transactions into record batches of up to `batch_size` rows.
+pub fn to_record_batches<'a, T, I>(transactions: I, batch_size: usize) -> RecordBatches<'a>
+where
+    T: Borrow<Transaction>,
+    I: IntoIterator<Item = Result<T, ParserError>>,
+    I::IntoIter: 'a,
+{
+    let batch_size = batch_size.max(1);
+    let mut transactions = transactions.into_iter().peekable();
+    let mut failed = false;
+
+    Box::new(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        transactions.peek()?;
+        let batch = transactions
+            .by_ref()
+            .take(batch_size)
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|batch| to_record_batch(&batch));
+        failed = batch.is_err();
+        Some(batch)
+    }))
+}
+
+/// Reads transactions from record batches one at a time.
+pub fn from_record_batches<'a, I>(batches: I) -> Transactions<'a>
+where
+    I: IntoIterator<Item = Result<RecordBatch, ArrowError>>,
+    I::IntoIter: 'a,
+{
+    read_record_batches(batches, ParserError::InvalidArrowFormat)
+}
+
+/// Reads transactions from record batches, creating errors with the `invalid` function of the
+/// format the batches were read from.
+pub(crate) fn read_record_batches<'a, I>(
+    batches: I,
+    invalid: fn(String) -> ParserError,
+) -> Transactions<'a>
+where
+    I: IntoIterator<Item = Result<RecordBatch, ArrowError>>,
+    I::IntoIter: 'a,
+{
+    let mut batches = batches.into_iter();
+    let mut transactions = vec![].into_iter();
+    let mut failed = false;
+
+    Box::new(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        loop {
+            if let Some(transaction) = transactions.next() {
+                return Some(Ok(transaction));
+            }
+            let batch = batches
+                .next()?
+                .map_err(|e| invalid(e.to_string()))
+                .and_then(|batch| read_record_batch(&batch, invalid));
+            match batch {
+                Ok(batch) => transactions = batch.into_iter(),
+                Err(e) => {
+                    failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }))
+}
+
+fn read_record_batch(
+    batch: &RecordBatch,
+    invalid: fn(String) -> ParserError,
+) -> Result<Vec<Transaction>, ParserError> {
+    let mut transactions = vec![Transaction::default(); batch.num_rows()];
+
+    for field in Field::ALL.iter() {
+        let name = field.key();
+        let column = batch
+            .column_by_name(name)
+            .ok_or_else(|| invalid(format!("Missing column: {}", name)))?;
+        if column.null_count() > 0 {
+            return Err(invalid(format!("Null values in column: {}", name)));
+        }
+        let wrong_type = || {
+            invalid(format!(
+                "Invalid type of column {}: {}",
+                name,
+                column.data_type()
+            ))
+        };
+
+        match field {
+            Field::Timestamp => {
+                let values = column
+                    .as_any()
+                    .downcast_ref::<TimestampMillisecondArray>()
+                    .ok_or_else(wrong_type)?;
+                for (transaction, value) in transactions.iter_mut().zip(values.values()) {
+                    transaction.timestamp = u64::try_from(*value)
+                        .map_err(|_| invalid(format!("Negative timestamp: {}", value)))?;
+                }
+            }
+            Field::TxType | Field::Status | Field::Description => {
+                let values = column
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .ok_or_else(wrong_type)?;
+                for (transaction, value) in transactions.iter_mut().zip(values.iter()) {
+                    field.set_value(transaction, value.unwrap_or_default())?;
+                }
+            }
+            _ => {
+                let values = column
+                    .as_any()
+                    .downcast_ref::<UInt64Array>()
+                    .ok_or_else(wrong_type)?;
+                for (transaction, value) in transactions.iter_mut().zip(values.values()) {
+                    match field {
+                        Field::TxId => transaction.tx_id = *value,
+                        Field::FromUserId => transaction.from_user_id = *value,
+                        Field::ToUserId => transaction.to_user_id = *value,
+                        _ => transaction.amount = *value,
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CsvParserFactory, Parser, ParserFactory};
+    use arrow_array::Int64Array;
+    use std::fs::File;
+
+    #[test]
+    fn it_converts_record_batches() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        let batches = to_record_batches(transactions.iter().map(Ok), 300)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect::<Vec<_>>(),
+            [300, 300, 300, 100]
+        );
+        assert_eq!(batches[0].schema(), transaction_schema());
+
+        let read = from_record_batches(batches.into_iter().map(Ok))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, transactions);
+    }
+
+    #[test]
+    fn it_is_invalid_record_batch() {
+        let batch = to_record_batch(&[Transaction::default()]).unwrap();
+        let without_amount = batch.project(&[0, 1, 2, 3, 5, 6, 7]).unwrap();
+        let mut columns = batch.columns().to_vec();
+        columns[4] = Arc::new(Int64Array::from(vec![1]));
+        let wrong_type = RecordBatch::try_from_iter(
+            batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .zip(columns),
+        )
+        .unwrap();
+
+        for batch in [without_amount, wrong_type] {
+            assert!(
+                from_record_batch(&batch)
+                    .is_err_and(|e| matches!(e, ParserError::InvalidArrowFormat(_)))
+            );
+        }
+    }
+}
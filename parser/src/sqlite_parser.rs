@@ -0,0 +1,308 @@
+//! The `sqlite_parser` module provides functionality to parse financial transactions from SQLite
+//! databases.
+//!
+//! Transactions are written to a typed table, `transactions` by default, with one column per
+//! field named like the field in lower case, e.g. `tx_id`. Numbers are `INTEGER` columns and the
+//! other fields are `TEXT` columns, and the table is indexed on `tx_id`, the user IDs and
+//! `timestamp`. Transactions are read back from the table or from the rows of a user-supplied
+//! query, whose columns are found by name, so they may be in any order and extra columns are
+//! ignored. Databases are built and read in memory, as SQLite needs random access to them.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use rusqlite::Connection;
+use rusqlite::types::{Value, ValueRef};
+use std::borrow::Borrow;
+use std::io::{Read, Write};
+
+/// The schema of the database that is read or written.
+const SCHEMA: &str = "main";
+
+/// The `SqliteParserFactory` struct is a factory for creating SQLite parsers.
+pub struct SqliteParserFactory;
+
+impl ParserFactory for SqliteParserFactory {
+    type Parser = SqliteParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        SqliteParser::default()
+    }
+}
+
+/// The `SqliteParser` struct is a parser for SQLite databases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteParser {
+    table: String,
+    query: Option<String>,
+}
+
+impl Default for SqliteParser {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TABLE.to_string(), None)
+    }
+}
+
+impl Parser for SqliteParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut transactions = vec![];
+        let result = self.read_records(r, &mut transactions);
+        Box::new(
+            transactions
+                .into_iter()
+                .map(Ok)
+                .chain(result.err().map(Err)),
+        )
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl SqliteParser {
+    /// The name of the table transactions are written to and read from by default.
+    pub const DEFAULT_TABLE: &'static str = "transactions";
+
+    /// Creates a parser writing transactions to `table` and reading them from the rows of
+    /// `query`, or from `table` without a query.
+    pub fn new(table: String, query: Option<String>) -> Self {
+        Self { table, query }
+    }
+
+    fn read_records<R: Read>(
+        &self,
+        mut r: R,
+        transactions: &mut Vec<Transaction>,
+    ) -> Result<(), ParserError> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+        let mut connection = Connection::open_in_memory().map_err(sqlite_error)?;
+        connection
+            .deserialize_read_exact(SCHEMA, buffer.as_slice(), buffer.len(), true)
+            .map_err(sqlite_error)?;
+
+        let query = match &self.query {
+            Some(query) => query.clone(),
+            None => format!("SELECT * FROM {}", quote(&self.table)),
+        };
+        let mut statement = connection.prepare(&query).map_err(sqlite_error)?;
+        let fields = statement
+            .column_names()
+            .into_iter()
+            .map(|name| Field::ALL.into_iter().find(|field| field.key() == name))
+            .collect::<Vec<_>>();
+
+        let mut rows = statement.query([]).map_err(sqlite_error)?;
+        while let Some(row) = rows.next().map_err(sqlite_error)? {
+            let mut record = Record::new(invalid);
+            for (index, field) in fields.iter().enumerate() {
+                let Some(field) = field else {
+                    continue;
+                };
+                let value = match row.get_ref(index).map_err(sqlite_error)? {
+                    ValueRef::Integer(number) => u64::try_from(number)
+                        .map_err(|_| invalid(format!("Negative {}: {}", field.key(), number)))?
+                        .to_string(),
+                    ValueRef::Text(text) => String::from_utf8(text.to_vec())
+                        .map_err(|e| invalid(format!("{}: {}", field.key(), e)))?,
+                    value => {
+                        return Err(invalid(format!(
+                            "Invalid type of {}: {}",
+                            field.key(),
+                            value.data_type()
+                        )));
+                    }
+                };
+                record.set(*field, &value)?;
+            }
+            transactions.push(record.finish()?);
+        }
+        Ok(())
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut connection = Connection::open_in_memory().map_err(sqlite_error)?;
+        let table = quote(&self.table);
+        let columns = Field::ALL
+            .iter()
+            .map(|field| {
+                let data_type = if field.is_numeric() {
+                    "INTEGER"
+                } else {
+                    "TEXT"
+                };
+                format!("{} {} NOT NULL", field.key(), data_type)
+            })
+            .collect::<Vec<_>>();
+        connection
+            .execute(
+                &format!("CREATE TABLE {} ({}) STRICT", table, columns.join(", ")),
+                [],
+            )
+            .map_err(sqlite_error)?;
+
+        let sql_transaction = connection.transaction().map_err(sqlite_error)?;
+        {
+            let keys = Field::ALL.map(|field| field.key());
+            let mut insert = sql_transaction
+                .prepare(&format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    keys.join(", "),
+                    vec!["?"; keys.len()].join(", ")
+                ))
+                .map_err(sqlite_error)?;
+            for transaction in transactions {
+                let transaction = transaction?;
+                let values = Field::ALL
+                    .iter()
+                    .map(|field| to_value(*field, transaction.borrow()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                insert
+                    .execute(rusqlite::params_from_iter(values))
+                    .map_err(sqlite_error)?;
+            }
+        }
+        for key in ["tx_id", "from_user_id", "to_user_id", "timestamp"] {
+            sql_transaction
+                .execute(
+                    &format!(
+                        "CREATE INDEX {} ON {} ({})",
+                        quote(&format!("{}_{}", self.table, key)),
+                        table,
+                        key
+                    ),
+                    [],
+                )
+                .map_err(sqlite_error)?;
+        }
+        sql_transaction.commit().map_err(sqlite_error)?;
+
+        writer.write_all(&connection.serialize(SCHEMA).map_err(sqlite_error)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn to_value(field: Field, transaction: &Transaction) -> Result<Value, ParserError> {
+    match field.number(transaction) {
+        Some(number) => i64::try_from(number)
+            .map(Value::Integer)
+            .map_err(|_| invalid(format!("Too large {}: {}", field.key(), number))),
+        None => Ok(Value::Text(field.value(transaction))),
+    }
+}
+
+/// Quotes an SQL identifier, such as a table name.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn sqlite_error(e: rusqlite::Error) -> ParserError {
+    invalid(e.to_string())
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidSqliteFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_sqlite() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        SqliteParserFactory
+            .create_parser()
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        assert!(buffer.starts_with(b"SQLite format 3\0"));
+
+        let read = SqliteParserFactory
+            .create_parser()
+            .read_from(&mut buffer.as_slice())
+            .unwrap();
+        assert_eq!(read, transactions);
+
+        let query = "SELECT description AS note, * FROM transactions \
+                     WHERE tx_type = 'TRANSFER' ORDER BY amount DESC LIMIT 2";
+        let read = SqliteParser::new("ignored".to_string(), Some(query.to_string()))
+            .read_from(&mut buffer.as_slice())
+            .unwrap();
+        let mut transfers = transactions
+            .into_iter()
+            .filter(|transaction| transaction.tx_type == crate::TransactionType::Transfer)
+            .collect::<Vec<_>>();
+        transfers.sort_by_key(|transaction| std::cmp::Reverse(transaction.amount));
+        assert_eq!(read, transfers[..2]);
+    }
+
+    #[test]
+    fn it_is_invalid_sqlite_format() {
+        let parser = SqliteParser::new("ledger".to_string(), None);
+        let mut buffer = vec![];
+        parser
+            .write_to(&mut buffer, &[Transaction::default()])
+            .unwrap();
+
+        let queries = [
+            "SELECT tx_id FROM ledger",
+            "SELECT -1 AS tx_id, * FROM ledger",
+            "SELECT * FROM transactions",
+        ];
+        for query in queries {
+            let parser = SqliteParser::new("ledger".to_string(), Some(query.to_string()));
+            assert!(
+                parser
+                    .read_from(&mut buffer.as_slice())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidSqliteFormat(_))),
+                "{}",
+                query
+            );
+        }
+        assert!(
+            SqliteParserFactory
+                .create_parser()
+                .read_from(&mut b"not sqlite".as_slice())
+                .is_err_and(|e| matches!(e, ParserError::InvalidSqliteFormat(_)))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! The `toml_parser` module provides functionality to parse financial transactions from TOML
+//! files.
+//!
+//! Transactions are the `[[transactions]]` array of tables of a file, each table with the
+//! lower-case field names as keys, e.g. `tx_id = 1000000000000000`, and `tx_type` and `status`
+//! in upper case, e.g. `"DEPOSIT"`. The `description` may be omitted when it is empty, so files
+//! are easy to write by hand, and unknown keys are rejected. As TOML integers are signed 64-bit
+//! numbers, writing larger IDs, amounts or timestamps fails with an error, so that files stay
+//! valid for other TOML tools. Documents are read and written in memory.
+
+use crate::error::ParserError;
+use crate::fixture::FixtureTransaction;
+use crate::transaction::{Field, Transaction};
+use crate::{Parser, ParserFactory, Transactions};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::io::{Read, Write};
+
+/// The `TomlParserFactory` struct is a factory for creating TOML parsers.
+pub struct TomlParserFactory;
+
+impl ParserFactory for TomlParserFactory {
+    type Parser = TomlParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        TomlParser
+    }
+}
+
+/// The `TomlParser` struct is a parser for TOML files.
+pub struct TomlParser;
+
+/// The `Document` struct is the root table of a TOML file.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document<T> {
+    #[serde(default = "Vec::new")]
+    transactions: Vec<T>,
+}
+
+impl Parser for TomlParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        match Self::read_records(r) {
+            Ok(transactions) => Box::new(transactions.into_iter().map(Ok)),
+            Err(e) => Box::new(std::iter::once(Err(e))),
+        }
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl TomlParser {
+    fn read_records<R: Read>(mut r: R) -> Result<Vec<Transaction>, ParserError> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+        let toml = String::from_utf8(buffer).map_err(|e| invalid(e.to_string()))?;
+        let document: Document<FixtureTransaction> =
+            toml::from_str(&toml).map_err(|e| invalid(e.to_string()))?;
+        Ok(document
+            .transactions
+            .into_iter()
+            .map(Transaction::from)
+            .collect())
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let transactions = transactions.into_iter().collect::<Result<Vec<_>, _>>()?;
+        for transaction in &transactions {
+            for field in Field::ALL {
+                if let Some(number) = field.number(transaction.borrow()) {
+                    i64::try_from(number)
+                        .map_err(|_| invalid(format!("Too large {}: {}", field.key(), number)))?;
+                }
+            }
+        }
+        let document = Document {
+            transactions: transactions.iter().map(T::borrow).collect(),
+        };
+
+        let toml = toml::to_string(&document).map_err(|e| invalid(e.to_string()))?;
+        writer.write_all(toml.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidTomlFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use crate::transaction::{TransactionStatus, TransactionType};
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_toml() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        TomlParser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(buffer.starts_with(b"[[transactions]]\ntx_id = 1000000000000000\n"));
+
+        let read = TomlParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, transactions);
+
+        let mut buffer = vec![];
+        TomlParser.write_to(&mut buffer, &[]).unwrap();
+        assert_eq!(TomlParser.read_from(&mut buffer.as_slice()).unwrap(), []);
+        assert_eq!(TomlParser.read_from(&mut "".as_bytes()).unwrap(), []);
+    }
+
+    #[test]
+    fn it_reads_handwritten_toml() {
+        let toml = r#"
+# A deposit without a description.
+[[transactions]]
+tx_id = 1
+tx_type = "DEPOSIT"
+from_user_id = 0
+to_user_id = 2
+amount = 100
+timestamp = 1633036860000
+status = "SUCCESS"
+
+[[transactions]]
+status = "PENDING"
+description = "Rent: October"
+tx_id = 2
+tx_type = "TRANSFER"
+from_user_id = 2
+to_user_id = 3
+amount = 50
+timestamp = 1633036920000
+"#;
+        let read = TomlParser.read_from(&mut toml.as_bytes()).unwrap();
+        assert_eq!(
+            read,
+            [
+                Transaction::new(
+                    1,
+                    TransactionType::Deposit,
+                    0,
+                    2,
+                    100,
+                    1633036860000,
+                    TransactionStatus::Success,
+                    "".to_string(),
+                ),
+                Transaction::new(
+                    2,
+                    TransactionType::Transfer,
+                    2,
+                    3,
+                    50,
+                    1633036920000,
+                    TransactionStatus::Pending,
+                    "Rent: October".to_string(),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_toml_format() {
+        for toml in [
+            "[[transaction]]\ntx_id = 1",
+            "[[transactions]]\ntx_id = 1\ntx_type = \"DEPOSIT\"",
+            "[[transactions]]\ntx_id = -1\ntx_type = \"DEPOSIT\"\nfrom_user_id = 0\nto_user_id = 0\namount = 0\ntimestamp = 0\nstatus = \"SUCCESS\"",
+            "[[transactions]]\ntx_id = 1\ntx_type = \"deposit\"\nfrom_user_id = 0\nto_user_id = 0\namount = 0\ntimestamp = 0\nstatus = \"SUCCESS\"",
+            "[[transactions]]\ntx_id = 1\ntx_type = \"DEPOSIT\"\nfrom_user_id = 0\nto_user_id = 0\namount = 0\ntimestamp = 0\nstatus = \"SUCCESS\"\ncurrency = \"EUR\"",
+            "[[transactions]\n",
+        ] {
+            assert!(
+                TomlParser
+                    .read_from(&mut toml.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidTomlFormat(_))),
+                "{}",
+                toml
+            );
+        }
+
+        let too_large = Transaction {
+            amount: u64::MAX,
+            ..Transaction::default()
+        };
+        assert!(
+            TomlParser
+                .write_to(&mut vec![], &[too_large])
+                .is_err_and(|e| matches!(e, ParserError::InvalidTomlFormat(_)))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! The `Field` enum names the fields of a financial transaction.
+//!
+//! Field names are parsed case-insensitively, so both `tx_id` and `TX_ID` are accepted.
+//! Fields are displayed in the upper-case form used by the CSV header.
+
+use crate::error::ParserError;
+use crate::transaction::Transaction;
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The `Field` enum names the fields of a financial transaction.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Field {
+    /// The ID of the transaction.
+    TxId,
+    /// The type of the transaction.
+    TxType,
+    /// The ID of the user who is sending the transaction.
+    FromUserId,
+    /// The ID of the user who is receiving the transaction.
+    ToUserId,
+    /// The amount of the transaction.
+    Amount,
+    /// The timestamp of the transaction.
+    Timestamp,
+    /// The status of the transaction.
+    Status,
+    /// The description of the transaction.
+    Description,
+}
+
+impl FromStr for Field {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "TX_ID" => Ok(Field::TxId),
+            "TX_TYPE" => Ok(Field::TxType),
+            "FROM_USER_ID" => Ok(Field::FromUserId),
+            "TO_USER_ID" => Ok(Field::ToUserId),
+            "AMOUNT" => Ok(Field::Amount),
+            "TIMESTAMP" => Ok(Field::Timestamp),
+            "STATUS" => Ok(Field::Status),
+            "DESCRIPTION" => Ok(Field::Description),
+            _ => Err(ParserError::UnknownField(s.to_string())),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::TxId => write!(f, "TX_ID"),
+            Field::TxType => write!(f, "TX_TYPE"),
+            Field::FromUserId => write!(f, "FROM_USER_ID"),
+            Field::ToUserId => write!(f, "TO_USER_ID"),
+            Field::Amount => write!(f, "AMOUNT"),
+            Field::Timestamp => write!(f, "TIMESTAMP"),
+            Field::Status => write!(f, "STATUS"),
+            Field::Description => write!(f, "DESCRIPTION"),
+        }
+    }
+}
+
+impl Field {
+    /// All fields in the order of the CSV header.
+    pub const ALL: [Field; 8] = [
+        Field::TxId,
+        Field::TxType,
+        Field::FromUserId,
+        Field::ToUserId,
+        Field::Amount,
+        Field::Timestamp,
+        Field::Status,
+        Field::Description,
+    ];
+
+    /// Returns the lower-case name of this field used as a key in keyed formats, e.g. `tx_id`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Field::TxId => "tx_id",
+            Field::TxType => "tx_type",
+            Field::FromUserId => "from_user_id",
+            Field::ToUserId => "to_user_id",
+            Field::Amount => "amount",
+            Field::Timestamp => "timestamp",
+            Field::Status => "status",
+            Field::Description => "description",
+        }
+    }
+
+    /// Returns whether the values of this field are numbers.
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, Field::TxType | Field::Status | Field::Description)
+    }
+
+    /// Returns the value of this field in a transaction as a number, or `None` for text fields.
+    pub fn number(&self, transaction: &Transaction) -> Option<u64> {
+        match self {
+            Field::TxId => Some(transaction.tx_id),
+            Field::FromUserId => Some(transaction.from_user_id),
+            Field::ToUserId => Some(transaction.to_user_id),
+            Field::Amount => Some(transaction.amount),
+            Field::Timestamp => Some(transaction.timestamp),
+            Field::TxType | Field::Status | Field::Description => None,
+        }
+    }
+
+    /// Returns the value of this field in a transaction as a string.
+    pub fn value(&self, transaction: &Transaction) -> String {
+        match self {
+            Field::TxId => transaction.tx_id.to_string(),
+            Field::TxType => transaction.tx_type.to_string(),
+            Field::FromUserId => transaction.from_user_id.to_string(),
+            Field::ToUserId => transaction.to_user_id.to_string(),
+            Field::Amount => transaction.amount.to_string(),
+            Field::Timestamp => transaction.timestamp.to_string(),
+            Field::Status => transaction.status.to_string(),
+            Field::Description => transaction.description.clone(),
+        }
+    }
+
+    /// Parses a string and sets it as the value of this field in a transaction.
+    pub fn set_value(&self, transaction: &mut Transaction, value: &str) -> Result<(), ParserError> {
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| ParserError::InvalidValue(format!("{}: {}", self, value)))
+        };
+        match self {
+            Field::TxId => transaction.tx_id = number()?,
+            Field::TxType => transaction.tx_type = value.parse()?,
+            Field::FromUserId => transaction.from_user_id = number()?,
+            Field::ToUserId => transaction.to_user_id = number()?,
+            Field::Amount => transaction.amount = number()?,
+            Field::Timestamp => transaction.timestamp = number()?,
+            Field::Status => transaction.status = value.parse()?,
+            Field::Description => transaction.description = value.to_string(),
+        }
+        Ok(())
+    }
+
+    /// Compares the values of this field in two transactions.
+    pub fn compare(&self, a: &Transaction, b: &Transaction) -> Ordering {
+        match self {
+            Field::TxId => a.tx_id.cmp(&b.tx_id),
+            Field::TxType => a.tx_type.cmp(&b.tx_type),
+            Field::FromUserId => a.from_user_id.cmp(&b.from_user_id),
+            Field::ToUserId => a.to_user_id.cmp(&b.to_user_id),
+            Field::Amount => a.amount.cmp(&b.amount),
+            Field::Timestamp => a.timestamp.cmp(&b.timestamp),
+            Field::Status => a.status.cmp(&b.status),
+            Field::Description => a.description.cmp(&b.description),
+        }
+    }
+}
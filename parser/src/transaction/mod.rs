@@ -9,16 +9,21 @@
 //! - `timestamp`: The timestamp of the transaction.
 //! - `status`: The status of the transaction.
 //! - `description`: The description of the transaction.
+//!
+//! Transactions are serialized with serde as maps from the field names to their values.
 
+mod field;
 mod status;
 mod r#type;
 
+pub use field::Field;
+use serde::{Deserialize, Serialize};
 pub use status::TransactionStatus;
 use std::fmt::{Display, Formatter};
 pub use r#type::TransactionType;
 
 /// The `Transaction` struct represents a financial transaction.
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Transaction {
     /// The ID of the transaction.
     pub tx_id: u64,
@@ -54,6 +59,7 @@ impl Default for Transaction {
 }
 
 impl Transaction {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: u64,
         tx_type: TransactionType,
@@ -7,13 +7,15 @@
 //!
 //! This enum provides functionality to parse transaction statuses from strings,
 //! convert them to/from string representations, and define integer-based
-//! representations.
+//! representations. It is serialized with serde as its upper-case name, e.g. `SUCCESS`.
 use crate::error::ParserError;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::str::FromStr;
 
 /// The `TransactionStatus` enum represents the status of a financial transaction.
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum TransactionStatus {
     /// A successful transaction.
     Success,
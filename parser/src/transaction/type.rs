@@ -7,14 +7,16 @@
 //!
 //! This enum provides functionality to parse transaction types from strings,
 //! convert them to/from string representations, and define integer-based
-//! representations.
+//! representations. It is serialized with serde as its upper-case name, e.g. `DEPOSIT`.
 
 use crate::error::ParserError;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::str::FromStr;
 
 /// The `TransactionType` enum represents the type of financial transaction.
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum TransactionType {
     /// A transaction involving adding funds.
     Deposit,
@@ -2,8 +2,9 @@
 
 use crate::error::ParserError;
 use crate::transaction::{Transaction, TransactionStatus, TransactionType};
-use crate::{Parser, ParserFactory};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
 
 /// The `TxtParserFactory` struct is a factory for creating TXT parsers.
 pub struct TxtParserFactory;
@@ -24,12 +25,53 @@ impl Parser for TxtParser {
     where
         Self: Sized,
     {
-        let reader = BufReader::new(r);
-        let mut transactions: Vec<Transaction> = vec![];
+        self.read_iter(r).collect()
+    }
 
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut lines = BufReader::new(r).lines();
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut lines).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl TxtParser {
+    /// Reads lines up to the next blank line and assembles them into a transaction.
+    fn read_record<B: BufRead>(lines: &mut Lines<B>) -> Result<Option<Transaction>, ParserError> {
         let mut transaction = Transaction::default();
+        let mut is_empty = true;
 
-        for line in reader.lines() {
+        for line in lines.by_ref() {
             let line = line?;
 
             if line.starts_with("#") {
@@ -37,36 +79,33 @@ impl Parser for TxtParser {
             }
 
             if line.trim().is_empty() {
-                transactions.push(transaction);
-                transaction = Transaction::default();
-                continue;
+                if is_empty {
+                    continue;
+                }
+                return Ok(Some(transaction));
             }
 
             TxtParser::process_line(&mut transaction, line)?;
+            is_empty = false;
         }
 
-        Ok(transactions)
+        Ok((!is_empty).then_some(transaction))
     }
 
-    fn write_to<W: Write>(
-        &self,
+    fn write_records<W: Write, T: Borrow<Transaction>>(
         writer: &mut W,
-        transactions: &[Transaction],
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
     ) -> Result<(), ParserError> {
         let mut buf_writer = BufWriter::new(writer);
-        let mut current = 1;
-        for transaction in transactions {
-            let line = TxtParser::serialize_transaction(transaction, current);
-            current += 1;
+        for (current, transaction) in (1..).zip(transactions) {
+            let line = TxtParser::serialize_transaction(transaction?.borrow(), current);
             let line = format!("{}\n\n", line);
             buf_writer.write_all(line.as_bytes())?;
         }
         buf_writer.flush()?;
         Ok(())
     }
-}
 
-impl TxtParser {
     fn process_line(transaction: &mut Transaction, line: String) -> Result<(), ParserError> {
         let parts = line
             .split(':')
@@ -157,4 +196,17 @@ mod tests {
         assert!(TxtParser.read_from(&mut file).is_ok());
         std::fs::remove_file("../samples/records_example_out.txt").unwrap();
     }
+
+    #[test]
+    fn it_separates_txt_records_by_blank_lines() {
+        let txt = "\n\n# Record 1\nTX_ID: 1\nAMOUNT: 100\n\n\n\n# Record 2\nTX_ID: 2\nAMOUNT: 200";
+        let transactions = TxtParser.read_from(&mut txt.as_bytes()).unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| (transaction.tx_id, transaction.amount))
+                .collect::<Vec<_>>(),
+            [(1, 100), (2, 200)]
+        );
+    }
 }
@@ -0,0 +1,358 @@
+//! The `xlsx_parser` module provides functionality to parse financial transactions from Excel
+//! XLSX workbooks.
+//!
+//! Every sheet has a header row with the lower-case field names, e.g. `tx_id`, frozen and with
+//! filters, followed by one row per transaction. IDs are text cells, so spreadsheet programs keep
+//! all 19 digits, `amount` is a number cell and `timestamp` is a date cell in UTC. Transactions
+//! are written to a single `Transactions` sheet, or to one sheet per transaction type with
+//! `XlsxSheets::PerType`. All sheets are read in order, and their columns are found by name, so
+//! they may be in any order and extra columns are ignored. Workbooks are built and read in
+//! memory, as they are zip archives.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use calamine::{Data, Reader, Xlsx};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io::{Cursor, Read, Write};
+use std::str::FromStr;
+
+/// The largest number that a spreadsheet number cell holds exactly.
+const MAX_EXACT_NUMBER: u64 = 1 << 53;
+/// The Excel serial date of the Unix epoch, 1970-01-01.
+const UNIX_EPOCH_SERIAL: f64 = 25569.0;
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+/// The name of the sheet with all transactions.
+const SHEET: &str = "Transactions";
+
+/// The `XlsxSheets` enum represents how transactions are distributed over sheets.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum XlsxSheets {
+    /// All transactions are on one sheet.
+    #[default]
+    Single,
+    /// Transactions are on a sheet per transaction type, named like the type.
+    PerType,
+}
+
+impl FromStr for XlsxSheets {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "single" => Ok(XlsxSheets::Single),
+            "per-type" | "per_type" => Ok(XlsxSheets::PerType),
+            _ => Err(ParserError::InvalidValue(format!("XLSX sheets: {}", s))),
+        }
+    }
+}
+
+impl Display for XlsxSheets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XlsxSheets::Single => write!(f, "single"),
+            XlsxSheets::PerType => write!(f, "per-type"),
+        }
+    }
+}
+
+/// The `XlsxParserFactory` struct is a factory for creating XLSX parsers.
+pub struct XlsxParserFactory;
+
+impl ParserFactory for XlsxParserFactory {
+    type Parser = XlsxParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        XlsxParser::default()
+    }
+}
+
+/// The `XlsxParser` struct is a parser for XLSX workbooks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct XlsxParser {
+    sheets: XlsxSheets,
+}
+
+impl Parser for XlsxParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut transactions = vec![];
+        let result = Self::read_records(r, &mut transactions);
+        Box::new(
+            transactions
+                .into_iter()
+                .map(Ok)
+                .chain(result.err().map(Err)),
+        )
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl XlsxParser {
+    /// Creates a parser distributing transactions over sheets as selected.
+    pub fn new(sheets: XlsxSheets) -> Self {
+        Self { sheets }
+    }
+
+    fn read_records<R: Read>(
+        mut r: R,
+        transactions: &mut Vec<Transaction>,
+    ) -> Result<(), ParserError> {
+        let mut buffer = vec![];
+        r.read_to_end(&mut buffer)?;
+        let mut workbook: Xlsx<_> = calamine::open_workbook_from_rs(Cursor::new(buffer))
+            .map_err(|e: calamine::XlsxError| invalid(e.to_string()))?;
+
+        for (sheet, range) in workbook.worksheets() {
+            let first_row = range.start().map_or(0, |(row, _)| row);
+            let mut rows = range.rows();
+            let Some(header) = rows.next() else {
+                continue;
+            };
+            let fields = header
+                .iter()
+                .map(|cell| {
+                    let name = cell.to_string();
+                    Field::ALL
+                        .into_iter()
+                        .find(|field| field.key() == name.trim())
+                })
+                .collect::<Vec<_>>();
+
+            for (index, row) in rows.enumerate() {
+                if row.iter().all(|cell| *cell == Data::Empty) {
+                    continue;
+                }
+                let at = |e: ParserError| match e {
+                    ParserError::InvalidXlsxFormat(message) => invalid(format!(
+                        "{}, row {}: {}",
+                        sheet,
+                        first_row as usize + index + 2,
+                        message
+                    )),
+                    e => e,
+                };
+                let mut record = Record::new(invalid);
+                for (field, cell) in fields.iter().zip(row) {
+                    let Some(field) = field else {
+                        continue;
+                    };
+                    let value = Self::cell_value(*field, cell).map_err(at)?;
+                    record.set(*field, &value).map_err(at)?;
+                }
+                transactions.push(record.finish().map_err(at)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a cell as the text of a field.
+    fn cell_value(field: Field, cell: &Data) -> Result<String, ParserError> {
+        let number = |number: f64| {
+            if number.fract() == 0.0 && (0.0..=MAX_EXACT_NUMBER as f64).contains(&number) {
+                Ok((number as u64).to_string())
+            } else {
+                Err(invalid(format!("Invalid {}: {}", field.key(), number)))
+            }
+        };
+
+        match (field, cell) {
+            (Field::Timestamp, Data::DateTime(date)) => {
+                let millis = ((date.as_f64() - UNIX_EPOCH_SERIAL) * MILLIS_PER_DAY).round();
+                number(millis)
+            }
+            (Field::Timestamp, _) => Err(invalid(format!("Invalid timestamp: {}", cell))),
+            (_, Data::Float(value)) => number(*value),
+            (_, Data::Int(value)) => number(*value as f64),
+            (_, Data::String(text)) => Ok(text.clone()),
+            (_, Data::Empty) => Ok(String::new()),
+            _ => Err(invalid(format!("Invalid {}: {}", field.key(), cell))),
+        }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut workbook = Workbook::new();
+        let date_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss.000");
+        let sheets = match self.sheets {
+            XlsxSheets::Single => vec![SHEET.to_string()],
+            XlsxSheets::PerType => (0..)
+                .map_while(TransactionType::from_repr)
+                .map(|tx_type| tx_type.to_string())
+                .collect(),
+        };
+        for sheet in &sheets {
+            let worksheet = workbook
+                .add_worksheet()
+                .set_name(sheet)
+                .map_err(xlsx_error)?;
+            for (column, field) in Field::ALL.iter().enumerate() {
+                worksheet
+                    .write_string(0, column as u16, field.key())
+                    .map_err(xlsx_error)?;
+            }
+        }
+
+        let mut rows = vec![0; sheets.len()];
+        for transaction in transactions {
+            let transaction = transaction?;
+            let transaction = transaction.borrow();
+            let sheet = match self.sheets {
+                XlsxSheets::Single => 0,
+                XlsxSheets::PerType => transaction.tx_type as usize,
+            };
+            let worksheet = workbook.worksheet_from_index(sheet).map_err(xlsx_error)?;
+            rows[sheet] += 1;
+
+            for (column, field) in Field::ALL.iter().enumerate() {
+                let (row, column) = (rows[sheet], column as u16);
+                match field {
+                    Field::Amount if transaction.amount > MAX_EXACT_NUMBER => {
+                        return Err(invalid(format!("Too large amount: {}", transaction.amount)));
+                    }
+                    Field::Amount => worksheet.write_number(row, column, transaction.amount as f64),
+                    Field::Timestamp => worksheet.write_number_with_format(
+                        row,
+                        column,
+                        UNIX_EPOCH_SERIAL + transaction.timestamp as f64 / MILLIS_PER_DAY,
+                        &date_format,
+                    ),
+                    _ => worksheet.write_string(row, column, field.value(transaction)),
+                }
+                .map_err(xlsx_error)?;
+            }
+        }
+
+        for (sheet, rows) in rows.into_iter().enumerate() {
+            let worksheet = workbook.worksheet_from_index(sheet).map_err(xlsx_error)?;
+            worksheet
+                .autofilter(0, 0, rows, Field::ALL.len() as u16 - 1)
+                .map_err(xlsx_error)?;
+            worksheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+            worksheet.autofit();
+        }
+
+        writer.write_all(&workbook.save_to_buffer().map_err(xlsx_error)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn xlsx_error(e: XlsxError) -> ParserError {
+    invalid(e.to_string())
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidXlsxFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_xlsx() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+
+        let mut buffer = vec![];
+        XlsxParserFactory
+            .create_parser()
+            .write_to(&mut buffer, &transactions)
+            .unwrap();
+        let mut workbook: Xlsx<_> =
+            calamine::open_workbook_from_rs(Cursor::new(buffer.clone())).unwrap();
+        assert_eq!(workbook.sheet_names(), [SHEET]);
+        let range = workbook.worksheet_range(SHEET).unwrap();
+        assert_eq!(range.get((0, 0)), Some(&Data::String("tx_id".to_string())));
+        assert!(matches!(range.get((1, 0)), Some(Data::String(_))));
+        assert!(matches!(range.get((1, 5)), Some(Data::DateTime(_))));
+
+        let read = XlsxParser::default()
+            .read_from(&mut buffer.as_slice())
+            .unwrap();
+        assert_eq!(read, transactions);
+
+        let parser = XlsxParser::new(XlsxSheets::PerType);
+        let mut buffer = vec![];
+        parser.write_to(&mut buffer, &transactions).unwrap();
+        let mut read = parser.read_from(&mut buffer.as_slice()).unwrap();
+        let mut expected = transactions.clone();
+        read.sort_by_key(|transaction| transaction.tx_id);
+        expected.sort_by_key(|transaction| transaction.tx_id);
+        assert_eq!(read, expected);
+    }
+
+    #[test]
+    fn it_is_invalid_xlsx_format() {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        // All cells are text, so the timestamp is not a date.
+        for (column, field) in Field::ALL.iter().enumerate() {
+            worksheet
+                .write_string(0, column as u16, field.key())
+                .unwrap();
+            worksheet
+                .write_string(1, column as u16, field.value(&Transaction::default()))
+                .unwrap();
+        }
+        let buffer = workbook.save_to_buffer().unwrap();
+
+        let too_large = Transaction {
+            amount: MAX_EXACT_NUMBER + 1,
+            ..Transaction::default()
+        };
+        let parser = XlsxParser::default();
+        assert!(
+            parser
+                .read_from(&mut buffer.as_slice())
+                .is_err_and(|e| e.to_string().contains("Sheet1, row 2"))
+        );
+        assert!(
+            parser
+                .read_from(&mut b"PK not xlsx".as_slice())
+                .is_err_and(|e| matches!(e, ParserError::InvalidXlsxFormat(_)))
+        );
+        assert!(
+            parser
+                .write_to(&mut vec![], &[too_large])
+                .is_err_and(|e| matches!(e, ParserError::InvalidXlsxFormat(_)))
+        );
+    }
+}
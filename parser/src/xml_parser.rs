@@ -0,0 +1,535 @@
+//! The `xml_parser` module provides functionality to parse financial transactions from XML files.
+//!
+//! Transactions are stored in a `Transactions` root element, one `Transaction` element each:
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <Transactions>
+//!   <Transaction>
+//!     <TxId>1000000000000000</TxId>
+//!     <TxType>DEPOSIT</TxType>
+//!     <FromUserId>0</FromUserId>
+//!     <ToUserId>9223372036854775807</ToUserId>
+//!     <Amount>100</Amount>
+//!     <Timestamp>1633036860000</Timestamp>
+//!     <Status>FAILURE</Status>
+//!     <Description>Record number 1</Description>
+//!   </Transaction>
+//! </Transactions>
+//! ```
+//!
+//! With `XmlStyle::Attributes` the fields are written as attributes instead, e.g.
+//! `<Transaction TxId="1000000000000000" TxType="DEPOSIT" ... />`. Both styles are
+//! accepted when reading, and every field is required. A document must have a single
+//! `Transactions` root with every `Transaction` directly inside it. `XmlParser::xsd` returns
+//! the XML Schema of a style.
+
+use crate::error::ParserError;
+use crate::record::Record;
+use crate::transaction::{Field, Transaction, TransactionStatus, TransactionType};
+use crate::{Parser, ParserFactory, Transactions};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
+
+const ROOT: &str = "Transactions";
+const TRANSACTION: &str = "Transaction";
+
+/// The `Root` enum is the place of a reader relative to the root element of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Root {
+    /// Before the start tag of the root.
+    Before,
+    /// Inside the root.
+    Inside,
+    /// After the end tag of the root.
+    After,
+}
+
+/// The `XmlStyle` enum represents how transaction fields are stored in XML.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum XmlStyle {
+    /// Each field is a child element of the transaction.
+    #[default]
+    Elements,
+    /// Each field is an attribute of the transaction.
+    Attributes,
+}
+
+impl FromStr for XmlStyle {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "elements" => Ok(XmlStyle::Elements),
+            "attributes" => Ok(XmlStyle::Attributes),
+            _ => Err(ParserError::InvalidValue(format!("XML style: {}", s))),
+        }
+    }
+}
+
+impl Display for XmlStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlStyle::Elements => write!(f, "elements"),
+            XmlStyle::Attributes => write!(f, "attributes"),
+        }
+    }
+}
+
+/// The `XmlParserFactory` struct is a factory for creating XML parsers.
+pub struct XmlParserFactory;
+
+impl ParserFactory for XmlParserFactory {
+    type Parser = XmlParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        XmlParser::default()
+    }
+}
+
+/// The `XmlParser` struct is a parser for XML files.
+#[derive(Default)]
+pub struct XmlParser {
+    style: XmlStyle,
+}
+
+impl Parser for XmlParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        self.write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        let mut reader = Reader::from_reader(BufReader::new(r));
+        let mut buffer = vec![];
+        let mut root = Root::Before;
+        let mut failed = false;
+
+        Box::new(std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            let record = Self::read_record(&mut reader, &mut buffer, &mut root).transpose();
+            failed = matches!(record, Some(Err(_)));
+            record
+        }))
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        self.write_records(writer, transactions)
+    }
+}
+
+impl XmlParser {
+    /// Creates a parser for XML files that writes fields in the given style.
+    pub fn new(style: XmlStyle) -> Self {
+        Self { style }
+    }
+
+    /// Returns the XML Schema (XSD) of the files written by the parser.
+    pub fn xsd(&self) -> String {
+        let enumeration = |name: &str, values: Vec<String>| {
+            let mut xsd = format!(
+                "  <xs:simpleType name=\"{}\">\n    <xs:restriction base=\"xs:string\">\n",
+                name
+            );
+            for value in values {
+                xsd += &format!("      <xs:enumeration value=\"{}\"/>\n", value);
+            }
+            xsd + "    </xs:restriction>\n  </xs:simpleType>\n"
+        };
+
+        let mut xsd = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\">\n",
+        );
+        xsd += &enumeration(
+            "TxType",
+            (0..)
+                .map_while(TransactionType::from_repr)
+                .map(|t| t.to_string())
+                .collect(),
+        );
+        xsd += &enumeration(
+            "Status",
+            (0..)
+                .map_while(TransactionStatus::from_repr)
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        xsd += &format!(
+            "  <xs:element name=\"{}\">\n    <xs:complexType>\n      <xs:sequence>\n        \
+             <xs:element name=\"{}\" minOccurs=\"0\" maxOccurs=\"unbounded\">\n          \
+             <xs:complexType>\n",
+            ROOT, TRANSACTION
+        );
+
+        let fields = Field::ALL.iter().map(|field| {
+            let kind = match field {
+                Field::TxType => "TxType",
+                Field::Status => "Status",
+                Field::Description => "xs:string",
+                _ => "xs:unsignedLong",
+            };
+            (xml_name(*field), kind)
+        });
+        match self.style {
+            XmlStyle::Elements => {
+                xsd += "            <xs:sequence>\n";
+                for (name, kind) in fields {
+                    xsd += &format!(
+                        "              <xs:element name=\"{}\" type=\"{}\"/>\n",
+                        name, kind
+                    );
+                }
+                xsd += "            </xs:sequence>\n";
+            }
+            XmlStyle::Attributes => {
+                for (name, kind) in fields {
+                    xsd += &format!(
+                        "            <xs:attribute name=\"{}\" type=\"{}\" use=\"required\"/>\n",
+                        name, kind
+                    );
+                }
+            }
+        }
+
+        xsd + "          </xs:complexType>\n        </xs:element>\n      </xs:sequence>\n    \
+               </xs:complexType>\n  </xs:element>\n</xs:schema>\n"
+    }
+
+    /// Reads events up to the end of the next transaction element, tracking where the reader
+    /// is relative to the root element in `root`.
+    fn read_record<B: BufRead>(
+        reader: &mut Reader<B>,
+        buffer: &mut Vec<u8>,
+        root: &mut Root,
+    ) -> Result<Option<Transaction>, ParserError> {
+        loop {
+            buffer.clear();
+            match reader.read_event_into(buffer).map_err(xml_error)? {
+                Event::Start(e)
+                    if *root == Root::Before && e.local_name().as_ref() == ROOT.as_bytes() =>
+                {
+                    *root = Root::Inside;
+                }
+                Event::Empty(e)
+                    if *root == Root::Before && e.local_name().as_ref() == ROOT.as_bytes() =>
+                {
+                    *root = Root::After;
+                }
+                Event::End(e)
+                    if *root == Root::Inside && e.local_name().as_ref() == ROOT.as_bytes() =>
+                {
+                    *root = Root::After;
+                }
+                Event::Start(e) | Event::Empty(e) if *root != Root::Inside => {
+                    return Err(ParserError::InvalidXmlFormat(format!(
+                        "Unexpected element outside of the {} root: {}",
+                        ROOT,
+                        String::from_utf8_lossy(e.name().as_ref())
+                    )));
+                }
+                Event::Start(e) if e.local_name().as_ref() == TRANSACTION.as_bytes() => {
+                    let mut record = record_from_attributes(&e)?;
+                    Self::read_elements(reader, &mut record)?;
+                    return record.finish().map(Some);
+                }
+                Event::Empty(e) if e.local_name().as_ref() == TRANSACTION.as_bytes() => {
+                    return record_from_attributes(&e)?.finish().map(Some);
+                }
+                Event::Start(e) | Event::Empty(e) => {
+                    return Err(ParserError::InvalidXmlFormat(format!(
+                        "Unexpected element: {}",
+                        String::from_utf8_lossy(e.name().as_ref())
+                    )));
+                }
+                Event::Text(e) if is_whitespace(&e) => continue,
+                Event::Text(_) | Event::CData(_) | Event::GeneralRef(_) | Event::End(_) => {
+                    return Err(ParserError::InvalidXmlFormat(
+                        "Unexpected content outside of a transaction".to_string(),
+                    ));
+                }
+                Event::Decl(_) | Event::Comment(_) | Event::PI(_) | Event::DocType(_) => continue,
+                Event::Eof if *root == Root::After => return Ok(None),
+                Event::Eof => {
+                    return Err(ParserError::InvalidXmlFormat(format!(
+                        "Missing {} root",
+                        ROOT
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Reads the field elements of a transaction up to its end tag.
+    fn read_elements<B: BufRead>(
+        reader: &mut Reader<B>,
+        record: &mut Record,
+    ) -> Result<(), ParserError> {
+        let mut buffer = vec![];
+        loop {
+            buffer.clear();
+            match reader.read_event_into(&mut buffer).map_err(xml_error)? {
+                Event::Start(e) => {
+                    let field = record.field(&String::from_utf8_lossy(e.local_name().as_ref()))?;
+                    let value = Self::read_text(reader)?;
+                    record.set(field, &value)?;
+                }
+                Event::Empty(e) => {
+                    let field = record.field(&String::from_utf8_lossy(e.local_name().as_ref()))?;
+                    record.set(field, "")?;
+                }
+                Event::End(_) => return Ok(()),
+                Event::Text(e) if is_whitespace(&e) => continue,
+                Event::Comment(_) | Event::PI(_) => continue,
+                Event::Eof => {
+                    return Err(ParserError::InvalidXmlFormat(
+                        "Unexpected end of file in a transaction".to_string(),
+                    ));
+                }
+                _ => {
+                    return Err(ParserError::InvalidXmlFormat(
+                        "Unexpected content in a transaction".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Reads the text of a field element up to its end tag.
+    fn read_text<B: BufRead>(reader: &mut Reader<B>) -> Result<String, ParserError> {
+        let mut buffer = vec![];
+        let mut text = String::new();
+        loop {
+            buffer.clear();
+            match reader.read_event_into(&mut buffer).map_err(xml_error)? {
+                Event::Text(e) => text += &e.decode().map_err(xml_error)?,
+                Event::CData(e) => text += &e.decode().map_err(xml_error)?,
+                Event::GeneralRef(e) => {
+                    let reference = format!("&{};", e.decode().map_err(xml_error)?);
+                    text += &quick_xml::escape::unescape(&reference).map_err(xml_error)?;
+                }
+                Event::Comment(_) => continue,
+                Event::End(_) => return Ok(text),
+                _ => {
+                    return Err(ParserError::InvalidXmlFormat(
+                        "Expected text in a field".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        &self,
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let mut buf_writer = BufWriter::new(writer);
+        buf_writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        writeln!(buf_writer, "<{}>", ROOT)?;
+        for transaction in transactions {
+            let element = self.serialize_transaction(transaction?.borrow());
+            buf_writer.write_all(element.as_bytes())?;
+        }
+        writeln!(buf_writer, "</{}>", ROOT)?;
+        buf_writer.flush()?;
+        Ok(())
+    }
+
+    fn serialize_transaction(&self, transaction: &Transaction) -> String {
+        match self.style {
+            XmlStyle::Elements => {
+                let mut element = format!("  <{}>\n", TRANSACTION);
+                for field in Field::ALL {
+                    let name = xml_name(field);
+                    element += &format!(
+                        "    <{}>{}</{}>\n",
+                        name,
+                        escape(&field.value(transaction)),
+                        name
+                    );
+                }
+                element + &format!("  </{}>\n", TRANSACTION)
+            }
+            XmlStyle::Attributes => {
+                let mut element = format!("  <{}", TRANSACTION);
+                for field in Field::ALL {
+                    element += &format!(
+                        " {}=\"{}\"",
+                        xml_name(field),
+                        escape(&field.value(transaction))
+                    );
+                }
+                element + "/>\n"
+            }
+        }
+    }
+}
+
+/// Creates a record of the fields of a transaction element from its attributes.
+fn record_from_attributes(element: &BytesStart) -> Result<Record, ParserError> {
+    let mut record = Record::with_names(invalid, xml_name);
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        let value = attribute.unescape_value().map_err(xml_error)?;
+        let field = record.field(&String::from_utf8_lossy(
+            attribute.key.local_name().as_ref(),
+        ))?;
+        record.set(field, &value)?;
+    }
+    Ok(record)
+}
+
+/// Returns the XML element or attribute name of a field.
+fn xml_name(field: Field) -> &'static str {
+    match field {
+        Field::TxId => "TxId",
+        Field::TxType => "TxType",
+        Field::FromUserId => "FromUserId",
+        Field::ToUserId => "ToUserId",
+        Field::Amount => "Amount",
+        Field::Timestamp => "Timestamp",
+        Field::Status => "Status",
+        Field::Description => "Description",
+    }
+}
+
+/// Escapes markup characters and the whitespace that XML readers would normalize.
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value)
+        .replace('\t', "&#9;")
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+}
+
+fn is_whitespace(text: &[u8]) -> bool {
+    text.iter().all(u8::is_ascii_whitespace)
+}
+
+fn xml_error(e: impl Display) -> ParserError {
+    invalid(e.to_string())
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidXmlFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_xml() {
+        let mut transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        transactions[0].description = "<a & \"b\">\n\ttab 'c'".to_string();
+
+        for style in [XmlStyle::Elements, XmlStyle::Attributes] {
+            let parser = XmlParser::new(style);
+            let mut buffer = vec![];
+            parser.write_to(&mut buffer, &transactions).unwrap();
+            assert_eq!(
+                parser.read_from(&mut buffer.as_slice()).unwrap(),
+                transactions
+            );
+        }
+    }
+
+    #[test]
+    fn it_reads_mixed_xml() {
+        let xml = r#"<?xml version="1.0"?>
+<!-- exported -->
+<Transactions>
+  <Transaction TxId="1" TxType="TRANSFER" Status="PENDING">
+    <FromUserId>2</FromUserId>
+    <ToUserId>3</ToUserId>
+    <Amount>400</Amount>
+    <Timestamp>5</Timestamp>
+    <Description><![CDATA[a <b>]]> &amp; &#x63;</Description>
+  </Transaction>
+  <Transaction TxId="2" TxType="DEPOSIT" FromUserId="0" ToUserId="1" Amount="1"
+      Timestamp="2" Status="SUCCESS" Description=""/>
+</Transactions>"#;
+        let transactions = XmlParser::default().read_from(&mut xml.as_bytes()).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].tx_type, TransactionType::Transfer);
+        assert_eq!(transactions[0].amount, 400);
+        assert_eq!(transactions[0].description, "a <b> & c");
+        assert_eq!(transactions[1].to_user_id, 1);
+
+        let empty = XmlParser::default().read_from(&mut "<Transactions/>".as_bytes());
+        assert_eq!(empty.unwrap(), []);
+    }
+
+    #[test]
+    fn it_is_invalid_xml_format() {
+        for xml in [
+            "<Transactions><Transaction TxId=\"1\"/></Transactions>",
+            "<Transactions><Transaction><TxId>1</TxId><TxId>2</TxId></Transaction></Transactions>",
+            "<Transactions><Transaction><TxId>x</TxId></Transaction></Transactions>",
+            "<Transactions><Transaction><Unknown/></Transaction></Transactions>",
+            "<Transactions><Record/></Transactions>",
+            "<Transactions>text</Transactions>",
+            "<Transactions><Transaction>",
+            "",
+            "<Transactions>",
+            "<Transaction TxId=\"1\" TxType=\"DEPOSIT\" FromUserId=\"0\" ToUserId=\"1\" \
+             Amount=\"1\" Timestamp=\"2\" Status=\"SUCCESS\" Description=\"\"/>",
+            "<Transactions/><Transactions/>",
+            "<Transactions></Transactions><Transactions></Transactions>",
+            "<Root><Transactions></Transactions></Root>",
+            "<Transactions><Transactions></Transactions></Transactions>",
+        ] {
+            assert!(
+                XmlParser::default()
+                    .read_from(&mut xml.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidXmlFormat(_))),
+                "{}",
+                xml
+            );
+        }
+    }
+
+    #[test]
+    fn it_generates_xsd() {
+        let xsd = XmlParser::new(XmlStyle::Attributes).xsd();
+        assert!(xsd.contains("<xs:enumeration value=\"WITHDRAWAL\"/>"));
+        assert!(
+            xsd.contains("<xs:attribute name=\"TxId\" type=\"xs:unsignedLong\" use=\"required\"/>")
+        );
+        assert!(
+            XmlParser::default()
+                .xsd()
+                .contains("<xs:element name=\"Status\" type=\"Status\"/>")
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! The `yaml_parser` module provides functionality to parse financial transactions from YAML
+//! files.
+//!
+//! A file is a single document with a sequence of transactions, each a mapping from the
+//! lower-case field names to their values, e.g. `- tx_id: 1000000000000000`, with `tx_type` and
+//! `status` in upper case, e.g. `DEPOSIT`. The `description` may be omitted when it is empty, so
+//! files are easy to write by hand, and unknown keys are rejected. Documents are read and written in memory.
+
+use crate::error::ParserError;
+use crate::fixture::FixtureTransaction;
+use crate::transaction::Transaction;
+use crate::{Parser, ParserFactory, Transactions};
+use std::borrow::Borrow;
+use std::io::{BufWriter, Read, Write};
+
+/// The `YamlParserFactory` struct is a factory for creating YAML parsers.
+pub struct YamlParserFactory;
+
+impl ParserFactory for YamlParserFactory {
+    type Parser = YamlParser;
+
+    fn create_parser(&self) -> Self::Parser {
+        YamlParser
+    }
+}
+
+/// The `YamlParser` struct is a parser for YAML files.
+pub struct YamlParser;
+
+impl Parser for YamlParser {
+    fn read_from<R: Read>(&self, r: &mut R) -> Result<Vec<Transaction>, ParserError>
+    where
+        Self: Sized,
+    {
+        self.read_iter(r).collect()
+    }
+
+    fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        Self::write_records(writer, transactions.iter().map(Ok))
+    }
+
+    fn read_iter<'a, R: Read + 'a>(&self, r: R) -> Transactions<'a>
+    where
+        Self: Sized,
+    {
+        match serde_yaml_ng::from_reader::<_, Vec<FixtureTransaction>>(r) {
+            Ok(transactions) => Box::new(transactions.into_iter().map(|t| Ok(t.into()))),
+            Err(e) => Box::new(std::iter::once(Err(yaml_error(e)))),
+        }
+    }
+
+    fn write_iter<W: Write, I: IntoIterator<Item = Result<Transaction, ParserError>>>(
+        &self,
+        writer: &mut W,
+        transactions: I,
+    ) -> Result<(), ParserError>
+    where
+        Self: Sized,
+    {
+        Self::write_records(writer, transactions)
+    }
+}
+
+impl YamlParser {
+    fn write_records<W: Write, T: Borrow<Transaction>>(
+        writer: &mut W,
+        transactions: impl IntoIterator<Item = Result<T, ParserError>>,
+    ) -> Result<(), ParserError> {
+        let transactions = transactions.into_iter().collect::<Result<Vec<_>, _>>()?;
+        let transactions = transactions.iter().map(T::borrow).collect::<Vec<_>>();
+
+        let mut buf_writer = BufWriter::new(writer);
+        serde_yaml_ng::to_writer(&mut buf_writer, &transactions).map_err(yaml_error)?;
+        buf_writer.flush()?;
+        Ok(())
+    }
+}
+
+fn yaml_error(e: serde_yaml_ng::Error) -> ParserError {
+    invalid(e.to_string())
+}
+
+fn invalid(message: String) -> ParserError {
+    ParserError::InvalidYamlFormat(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CsvParserFactory;
+    use crate::transaction::{TransactionStatus, TransactionType};
+    use std::fs::File;
+
+    #[test]
+    fn it_reads_written_yaml() {
+        let transactions = CsvParserFactory
+            .create_parser()
+            .read_from(&mut File::open("../samples/records_example.csv").unwrap())
+            .unwrap();
+        let mut buffer = vec![];
+        YamlParser.write_to(&mut buffer, &transactions).unwrap();
+        assert!(buffer.starts_with(b"- tx_id: 1000000000000000\n  tx_type: DEPOSIT\n"));
+
+        let read = YamlParser.read_from(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, transactions);
+    }
+
+    #[test]
+    fn it_reads_handwritten_yaml() {
+        let yaml = "\
+# A deposit without a description.
+- tx_id: 1
+  tx_type: DEPOSIT
+  from_user_id: 0
+  to_user_id: 2
+  amount: 100
+  timestamp: 1633036860000
+  status: SUCCESS
+
+- status: PENDING
+  description: 'Rent: October'
+  tx_id: 2
+  tx_type: TRANSFER
+  from_user_id: 2
+  to_user_id: 3
+  amount: 50
+  timestamp: 1633036920000
+";
+        let read = YamlParser.read_from(&mut yaml.as_bytes()).unwrap();
+        assert_eq!(
+            read,
+            [
+                Transaction::new(
+                    1,
+                    TransactionType::Deposit,
+                    0,
+                    2,
+                    100,
+                    1633036860000,
+                    TransactionStatus::Success,
+                    "".to_string(),
+                ),
+                Transaction::new(
+                    2,
+                    TransactionType::Transfer,
+                    2,
+                    3,
+                    50,
+                    1633036920000,
+                    TransactionStatus::Pending,
+                    "Rent: October".to_string(),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_is_invalid_yaml_format() {
+        for yaml in [
+            "tx_id: 1",
+            "- tx_id: 1\n  tx_type: DEPOSIT",
+            "- tx_id: -1\n  tx_type: DEPOSIT\n  from_user_id: 0\n  to_user_id: 0\n  amount: 0\n  timestamp: 0\n  status: SUCCESS",
+            "- tx_id: 1\n  tx_type: deposit\n  from_user_id: 0\n  to_user_id: 0\n  amount: 0\n  timestamp: 0\n  status: SUCCESS",
+            "- tx_id: 1\n  tx_type: DEPOSIT\n  from_user_id: 0\n  to_user_id: 0\n  amount: 0\n  timestamp: 0\n  status: SUCCESS\n  currency: EUR",
+            "- [",
+        ] {
+            assert!(
+                YamlParser
+                    .read_from(&mut yaml.as_bytes())
+                    .is_err_and(|e| matches!(e, ParserError::InvalidYamlFormat(_))),
+                "{}",
+                yaml
+            );
+        }
+    }
+}